@@ -0,0 +1,281 @@
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::future::Future;
+
+// How problems are reported, `json` prints one diagnostic per line on stdout for CI and editors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum MessageFormat {
+    #[default]
+    Human,
+    Json,
+}
+
+// What a diagnostic is about, so a run can be summarised and tools can filter on it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiagnosticCode {
+    // A type reference that doesn't lead to a schema
+    UnresolvedReference,
+    // A type mortar couldn't work out, generated as `any` or `unknown`
+    AnyFallback,
+    InvalidSchema,
+    InvalidEndpoint,
+    // The same name or action group coming from two sources
+    SourceConflict,
+    BannedNamespace,
+    // Anything without a more specific code
+    Error,
+}
+
+impl fmt::Display for DiagnosticCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiagnosticCode::UnresolvedReference => write!(f, "unresolved reference"),
+            DiagnosticCode::AnyFallback => write!(f, "any fallback"),
+            DiagnosticCode::InvalidSchema => write!(f, "invalid schema"),
+            DiagnosticCode::InvalidEndpoint => write!(f, "invalid endpoint"),
+            DiagnosticCode::SourceConflict => write!(f, "source conflict"),
+            DiagnosticCode::BannedNamespace => write!(f, "banned namespace"),
+            DiagnosticCode::Error => write!(f, "error"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+// A problem found while generating, structured so CI can annotate it
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    pub code: DiagnosticCode,
+    pub severity: Severity,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    // JSON pointer into the swagger document, e.g. `#/components/schemas/FooDto`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pointer: Option<String>,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn error(code: DiagnosticCode, message: impl Into<String>) -> Self {
+        Diagnostic {
+            code,
+            severity: Severity::Error,
+            target: None,
+            pointer: None,
+            message: message.into(),
+            suggestion: None,
+        }
+    }
+
+    pub fn warning(code: DiagnosticCode, message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            ..Diagnostic::error(code, message)
+        }
+    }
+
+    pub fn at(mut self, pointer: impl Into<String>) -> Self {
+        self.pointer = Some(pointer.into());
+        self
+    }
+
+    pub fn suggest(mut self, suggestion: impl Into<String>) -> Self {
+        self.suggestion = Some(suggestion.into());
+        self
+    }
+
+    pub fn print_json(&self) {
+        match serde_json::to_string(self) {
+            Ok(json) => println!("{}", json),
+            Err(e) => log::error!("Failed to serialise diagnostic {:?}", e),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+
+        if let Some(pointer) = &self.pointer {
+            write!(f, " at {}", pointer)?;
+        }
+
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, ". {}", suggestion)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+// Several problems found together, e.g. every type in a banned namespace
+#[derive(Debug)]
+pub struct Diagnostics(pub Vec<Diagnostic>);
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, diagnostic) in self.0.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", diagnostic)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for Diagnostics {}
+
+// Context naming the target an error came from
+#[derive(Debug)]
+pub struct TargetFailed(pub String);
+
+impl fmt::Display for TargetFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to generate {}", self.0)
+    }
+}
+
+// Escapes a key for use in a JSON pointer, e.g. a path like `/api/users/{id}`
+pub fn pointer_segment(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+// Turns a plain error into a diagnostic at the given pointer, leaving ones that already are diagnostics alone
+pub fn locate(error: anyhow::Error, code: DiagnosticCode, pointer: &str) -> anyhow::Error {
+    let located = error
+        .chain()
+        .any(|cause| cause.is::<Diagnostic>() || cause.is::<Diagnostics>());
+    if located {
+        return error;
+    }
+
+    Diagnostic::error(code, format!("{:#}", error)).at(pointer).into()
+}
+
+// The diagnostics behind an error, or a general one made from its message
+pub fn from_error(error: &anyhow::Error) -> Vec<Diagnostic> {
+    let target = error.downcast_ref::<TargetFailed>().map(|t| t.0.clone());
+
+    let mut diagnostics = error
+        .chain()
+        .find_map(|cause| {
+            if let Some(diagnostics) = cause.downcast_ref::<Diagnostics>() {
+                return Some(diagnostics.0.clone());
+            }
+
+            cause.downcast_ref::<Diagnostic>().map(|d| vec![d.clone()])
+        })
+        .unwrap_or_else(|| vec![Diagnostic::error(DiagnosticCode::Error, format!("{:#}", error))]);
+
+    for diagnostic in &mut diagnostics {
+        diagnostic.target = diagnostic.target.take().or_else(|| target.clone());
+    }
+
+    diagnostics
+}
+
+tokio::task_local! {
+    // Targets run side by side, so each run keeps its own warnings
+    static WARNINGS: RefCell<Vec<Diagnostic>>;
+    // The part of the swagger document being worked on, for warnings that don't know where they come from
+    static LOCATION: RefCell<Option<String>>;
+}
+
+// Points warnings raised without a pointer at the given location until it's dropped
+pub struct Location {
+    previous: Option<String>,
+}
+
+impl Drop for Location {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        let _ = LOCATION.try_with(|location| location.replace(previous));
+    }
+}
+
+pub fn enter(pointer: impl Into<String>) -> Location {
+    let previous = LOCATION
+        .try_with(|location| location.replace(Some(pointer.into())))
+        .ok()
+        .flatten();
+
+    Location { previous }
+}
+
+// Records a warning against the run in progress to be listed once it's done, outside of a run it's logged straight away.
+// The same problem is often hit by every use of a type so repeats are only recorded once.
+pub fn warn(mut diagnostic: Diagnostic) {
+    if diagnostic.pointer.is_none() {
+        diagnostic.pointer = LOCATION
+            .try_with(|location| location.borrow().clone())
+            .ok()
+            .flatten();
+    }
+
+    let recorded = WARNINGS.try_with(|warnings| {
+        let mut warnings = warnings.borrow_mut();
+        if !warnings
+            .iter()
+            .any(|w| w.message == diagnostic.message && w.pointer == diagnostic.pointer)
+        {
+            log::debug!("{}", diagnostic);
+            warnings.push(diagnostic.clone());
+        }
+    });
+
+    if recorded.is_err() {
+        log::warn!("{}", diagnostic);
+    }
+}
+
+// Runs a future, returning the warnings raised while it ran
+pub async fn collect_warnings<F: Future>(future: F) -> (F::Output, Vec<Diagnostic>) {
+    let run = async {
+        let output = future.await;
+        let warnings = WARNINGS.with(|warnings| warnings.take());
+
+        (output, warnings)
+    };
+
+    WARNINGS
+        .scope(RefCell::new(vec![]), LOCATION.scope(RefCell::new(None), run))
+        .await
+}
+
+// e.g. `3 warnings (2 unresolved references, 1 any fallback)`
+pub fn summarise(warnings: &[Diagnostic]) -> String {
+    let mut counts = BTreeMap::new();
+    for warning in warnings {
+        *counts.entry(warning.code).or_insert(0) += 1;
+    }
+
+    let codes = counts
+        .iter()
+        .map(|(code, count)| plural(*count, &code.to_string()))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!("{} ({})", plural(warnings.len(), "warning"), codes)
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("{} {}", count, noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
@@ -0,0 +1,241 @@
+use crate::swagger::SwaggerApi;
+use anyhow::{anyhow, Context};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::io::{IsTerminal, Write as _};
+use std::path::Path;
+use tokio::time::{timeout, Duration};
+
+// Where Saffron and ASP.NET backends usually serve their swagger.json
+const SWAGGER_PATHS: [&str; 2] = ["/api/swagger.json", "/swagger/v1/swagger.json"];
+const BUILD_ID_PATH: &str = "/mortar/buildId";
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct InitOptions<'a> {
+    pub config_file: &'a Path,
+    pub backend_url: Option<String>,
+    pub output_dir: Option<String>,
+    pub swagger_endpoint: Option<String>,
+    pub mortar_endpoint: Option<String>,
+    pub force: bool,
+}
+
+// Writes a starting mortar.toml for a new portal from what the backend turns out to support
+pub async fn run_init(options: InitOptions<'_>, swagger_api: &SwaggerApi) -> anyhow::Result<()> {
+    if options.config_file.exists() && !options.force {
+        Err(anyhow!(
+            "{} already exists, pass --force to replace it",
+            options.config_file.display()
+        ))?;
+    }
+
+    let backend_url = match options.backend_url {
+        Some(url) => url,
+        None => prompt("Backend URL", "http://localhost:5000")?,
+    };
+    let backend_url = backend_url.trim_end_matches('/');
+
+    let output_dir = match options.output_dir {
+        Some(dir) => dir,
+        None => prompt("Output directory", "./src/mortar")?,
+    };
+
+    if !Path::new(&output_dir).is_relative() {
+        Err(anyhow!("Output directory must be relative"))?;
+    }
+
+    let candidates = match options.swagger_endpoint {
+        Some(endpoint) => vec![endpoint],
+        None => SWAGGER_PATHS
+            .iter()
+            .map(|path| format!("{}{}", backend_url, path))
+            .collect(),
+    };
+
+    let mut swagger = None;
+    for endpoint in &candidates {
+        let probe = timeout(PROBE_TIMEOUT, swagger_api.get_swagger_document(endpoint)).await;
+        if let Ok(Ok(document)) = probe {
+            swagger = Some((endpoint.clone(), document));
+            break;
+        }
+    }
+
+    let has_extensions = swagger
+        .as_ref()
+        .is_some_and(|(_, document)| has_mortar_extensions(document));
+
+    let swagger_endpoint = match &swagger {
+        Some((endpoint, _)) => {
+            log::info!("Found swagger.json at {}", endpoint);
+            endpoint.clone()
+        }
+        None => {
+            log::warn!(
+                "Couldn't find a swagger.json at {}, is the backend running?",
+                candidates.join(" or ")
+            );
+            candidates[0].clone()
+        }
+    };
+
+    if swagger.is_some() && !has_extensions {
+        log::warn!("The swagger.json has no x-mtr extensions, mortar needs them to name actions. Is saffron up to date?");
+    }
+
+    let mortar_endpoint = options
+        .mortar_endpoint
+        .unwrap_or_else(|| format!("{}{}", backend_url, BUILD_ID_PATH));
+    let probe = timeout(
+        PROBE_TIMEOUT,
+        swagger_api.get_current_build_id(&mortar_endpoint, None),
+    )
+    .await;
+    let has_build_id = matches!(probe, Ok(Ok(_)));
+
+    if !has_build_id {
+        log::warn!(
+            "No build id at {}, `mortar --watch` won't be able to tell when the backend restarts",
+            mortar_endpoint
+        );
+    }
+
+    let settings = settings_file(&swagger_endpoint, &mortar_endpoint, has_build_id, &output_dir)?;
+    tokio::fs::write(options.config_file, settings)
+        .await
+        .with_context(|| format!("Failed to write {}", options.config_file.display()))?;
+    log::info!("Wrote {}", options.config_file.display());
+
+    let package_json = options
+        .config_file
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join("package.json");
+
+    if package_json.exists() {
+        add_package_scripts(&package_json).await?;
+    }
+
+    Ok(())
+}
+
+fn prompt(question: &str, default: &str) -> anyhow::Result<String> {
+    // Nothing to ask when run from a script
+    if !std::io::stdin().is_terminal() {
+        return Ok(default.to_owned());
+    }
+
+    print!("{} [{}]: ", question, default);
+    std::io::stdout().flush()?;
+
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+
+    match answer.trim() {
+        "" => Ok(default.to_owned()),
+        answer => Ok(answer.to_owned()),
+    }
+}
+
+fn has_mortar_extensions(document: &serde_json::Value) -> bool {
+    document
+        .get("paths")
+        .and_then(|paths| paths.as_object())
+        .is_some_and(|paths| {
+            paths
+                .values()
+                .filter_map(|path| path.as_object())
+                .flat_map(|path| path.values())
+                .any(|endpoint| endpoint.get("x-mtr").is_some())
+        })
+}
+
+fn settings_file(
+    swagger_endpoint: &str,
+    mortar_endpoint: &str,
+    has_build_id: bool,
+    output_dir: &str,
+) -> anyhow::Result<String> {
+    let mut file = String::new();
+
+    writeln!(file, "# Created by `mortar init`, see the mortar readme for every setting")?;
+    writeln!(file)?;
+    writeln!(file, "# Where the backend serves its swagger.json")?;
+    writeln!(file, "swagger_endpoint = {}", toml_string(swagger_endpoint))?;
+    writeln!(file, "# Lets `mortar --watch` regenerate whenever the backend restarts")?;
+    if has_build_id {
+        writeln!(file, "mortar_endpoint = {}", toml_string(mortar_endpoint))?;
+    } else {
+        writeln!(file, "# mortar_endpoint = {}", toml_string(mortar_endpoint))?;
+    }
+    writeln!(file, "# Cleared and regenerated on every run, relative to where mortar runs")?;
+    writeln!(file, "output_dir = {}", toml_string(output_dir))?;
+    writeln!(file)?;
+    writeln!(file, "# Type date-time fields as dates and revive them in responses")?;
+    writeln!(file, "# revive_dates = true")?;
+    writeln!(file, "# How enums are emitted: const_object, enum, const_enum or union")?;
+    writeln!(file, "# enum_style = \"const_object\"")?;
+    writeln!(file, "# Regexes of namespaces to leave out, e.g. database entities")?;
+    writeln!(file, "# banned_namespaces = [\"RedRiver/Saffron/EntityFramework\"]")?;
+
+    Ok(file)
+}
+
+fn toml_string(value: &str) -> String {
+    toml::Value::String(value.to_owned()).to_string()
+}
+
+// JSON that keeps the order of object keys, serde_json's own Value sorts them
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum OrderedJson {
+    Object(IndexMap<String, OrderedJson>),
+    Array(Vec<OrderedJson>),
+    Value(serde_json::Value),
+}
+
+impl OrderedJson {
+    fn as_object_mut(&mut self) -> Option<&mut IndexMap<String, OrderedJson>> {
+        match self {
+            OrderedJson::Object(object) => Some(object),
+            _ => None,
+        }
+    }
+}
+
+// Adds `mortar` scripts to package.json, keeping the order of what's already there
+async fn add_package_scripts(path: &Path) -> anyhow::Result<()> {
+    let package = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let mut parsed: OrderedJson =
+        serde_json::from_str(&package).with_context(|| format!("{} isn't valid JSON", path.display()))?;
+
+    let scripts = parsed
+        .as_object_mut()
+        .context("package.json should be an object")?
+        .entry("scripts".to_owned())
+        .or_insert_with(|| OrderedJson::Object(IndexMap::new()))
+        .as_object_mut()
+        .context("package.json scripts should be an object")?;
+
+    if scripts.contains_key("mortar") {
+        log::info!("{} already has a mortar script", path.display());
+        return Ok(());
+    }
+
+    scripts.insert("mortar".to_owned(), OrderedJson::Value("mortar".into()));
+    scripts.insert("mortar:watch".to_owned(), OrderedJson::Value("mortar --watch".into()));
+
+    let mut updated = serde_json::to_string_pretty(&parsed)?;
+    updated.push('\n');
+
+    tokio::fs::write(path, updated)
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    log::info!("Added mortar scripts to {}", path.display());
+
+    Ok(())
+}
@@ -1,121 +1,482 @@
+mod diagnostics;
 mod formatter;
+mod init;
 mod module_codegen;
 mod string_tools;
 mod swagger;
 use anyhow::Context;
+use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::str::FromStr;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::{collections::HashMap, rc::Rc};
-use tokio::fs;
-use tokio::io::AsyncReadExt;
+use std::rc::Rc;
 
 mod parser;
 mod run_emit;
 mod settings;
 mod schema_resolver;
 
-use crate::parser::SwaggerParser;
 use crate::swagger::SwaggerApi;
-use settings::Settings;
-use tokio::time::{sleep, Duration};
+use diagnostics::{Diagnostic, MessageFormat};
+use settings::{HttpSettings, Settings, SettingsOverrides};
+use tokio::task::{JoinSet, LocalSet};
+use tokio::sync::watch;
+use tokio::time::{sleep, timeout, Duration};
 
 use clap::Parser;
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
+    #[clap(subcommand)]
+    command: Option<Command>,
+
     #[clap(short, long)]
     watch: bool,
 
+    /// Log more, -vv for everything
+    #[clap(short, long, global = true, action = clap::ArgAction::Count, conflicts_with = "quiet")]
+    verbose: u8,
+
+    /// Only log warnings, -qq for errors only
+    #[clap(short, long, global = true, action = clap::ArgAction::Count)]
+    quiet: u8,
+
+    /// Fail when generating raises warnings, e.g. unresolved references or types falling back to any
+    #[clap(long)]
+    deny_warnings: bool,
+
+    /// Generate everything that parsed when parts of the swagger are broken, still failing the run afterwards
     #[clap(long)]
+    keep_going: bool,
+
+    /// `json` prints warnings and errors as one JSON object per line on stdout, for CI annotations
+    #[clap(long, value_enum, default_value_t, global = true)]
+    message_format: MessageFormat,
+
+    /// Settings file to use instead of ./mortar.toml
+    #[clap(long, global = true)]
+    config: Option<PathBuf>,
+
+    #[clap(long, global = true)]
+    output_dir: Option<String>,
+
+    #[clap(long, global = true)]
+    swagger_endpoint: Option<String>,
+
+    #[clap(long, global = true)]
+    mortar_endpoint: Option<String>,
+
+    #[clap(long, global = true)]
     swagger_file: Option<PathBuf>,
 }
 
+// How each target is run, from the command line
+#[derive(Debug, Clone, Copy)]
+struct RunOptions {
+    watch: bool,
+    deny_warnings: bool,
+    keep_going: bool,
+    message_format: MessageFormat,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Write a mortar.toml for a new portal, checking what the backend supports
+    Init {
+        /// e.g. http://localhost:5000, asked for when not given
+        #[clap(long)]
+        backend_url: Option<String>,
+
+        /// Replace an existing mortar.toml
+        #[clap(long)]
+        force: bool,
+    },
+    /// Inspect the settings mortar runs with
+    #[clap(subcommand)]
+    Config(ConfigCommand),
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum ConfigCommand {
+    /// Print each target's effective settings and where every value came from
+    Show,
+    /// Print the JSON Schema of mortar.toml for editor completion and validation
+    Schema,
+}
+
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
-    let settings = Settings::new().context("Failed to create settings")?;
+    init_logging(args.verbose, args.quiet);
 
-    dbg!("{:?}", &settings);
+    let message_format = args.message_format;
+    let result = run(args);
 
-    // maintain swagger api for lifetime of program to avoid port exhaustion
-    let swagger_api = SwaggerApi::new();
+    if let (Err(e), MessageFormat::Json) = (&result, message_format) {
+        for diagnostic in diagnostics::from_error(e) {
+            diagnostic.print_json();
+        }
+    }
 
-    let mut last_build_id: Option<String> = None;
+    result
+}
 
-    let result: anyhow::Result<()> = tokio::runtime::Builder::new_current_thread()
+fn run(args: Args) -> anyhow::Result<()> {
+    let overrides = SettingsOverrides {
+        output_dir: args.output_dir.clone(),
+        swagger_endpoint: args.swagger_endpoint.clone(),
+        mortar_endpoint: args.mortar_endpoint.clone(),
+        swagger_file: args.swagger_file.clone(),
+    };
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
         .enable_time()
         .enable_io()
         .build()
-        .unwrap()
-        .block_on(async {
-            if let Some(fp) = args.swagger_file {
-                run_emit_from_file(&fp, &settings).await?;
-            } else {
-                loop {
-                    block_on_matching_build_id(&mut last_build_id, &swagger_api, &settings).await;
-                    println!("Running emit");
-                    run_emit::run_emit(&swagger_api, &settings).await?;
-
-                    if !args.watch {
-                        break;
-                    }
+        .unwrap();
+
+    match args.command {
+        Some(Command::Config(ConfigCommand::Show)) => {
+            return show_config(args.config.as_deref(), &overrides);
+        }
+        Some(Command::Config(ConfigCommand::Schema)) => {
+            println!("{}", serde_json::to_string_pretty(&Settings::json_schema())?);
+            return Ok(());
+        }
+        Some(Command::Init { backend_url, force }) => {
+            let options = init::InitOptions {
+                config_file: args.config.as_deref().unwrap_or(Path::new("mortar.toml")),
+                backend_url,
+                output_dir: overrides.output_dir,
+                swagger_endpoint: overrides.swagger_endpoint,
+                mortar_endpoint: overrides.mortar_endpoint,
+                force,
+            };
+
+            let http = Settings::load_http(args.config.as_deref())?;
+            let swagger_api = SwaggerApi::new(&http).context("Invalid http settings")?;
+            return runtime.block_on(init::run_init(options, &swagger_api));
+        }
+        None => {}
+    }
+
+    let targets = Settings::load_targets(args.config.as_deref(), &overrides)
+        .context("Failed to create settings")?;
+
+    // maintain swagger api for lifetime of program to avoid port exhaustion, targets with the same http settings share one
+    let mut swagger_apis: Vec<(HttpSettings, Rc<SwaggerApi>)> = vec![];
+    let mut targets_with_apis = Vec::with_capacity(targets.len());
+    for settings in targets {
+        let existing = swagger_apis.iter().find(|(http, _)| http == &settings.http);
+        let swagger_api = match existing {
+            Some((_, swagger_api)) => swagger_api.clone(),
+            None => {
+                let swagger_api = Rc::new(
+                    SwaggerApi::new(&settings.http)
+                        .with_context(|| format!("Invalid http settings for {}", settings.display_name()))?,
+                );
+                swagger_apis.push((settings.http.clone(), swagger_api.clone()));
+                swagger_api
+            }
+        };
+
+        targets_with_apis.push((settings, swagger_api));
+    }
+
+    let options = RunOptions {
+        watch: args.watch,
+        deny_warnings: args.deny_warnings,
+        keep_going: args.keep_going,
+        message_format: args.message_format,
+    };
+
+    let local = LocalSet::new();
+
+    let result: anyhow::Result<()> = runtime.block_on(local.run_until(async {
+            let (stop, stopped) = watch::channel(false);
+            // The first Ctrl+C or SIGTERM lets generating finish before stopping, a second exits straight away
+            tokio::task::spawn_local(async move {
+                if let Err(e) = shutdown_signal().await {
+                    log::debug!("Failed to listen for shutdown signals {:?}", e);
+                    return std::future::pending().await;
                 }
+
+                log::info!("Stopping, press Ctrl+C again to exit immediately");
+                let _ = stop.send(true);
+
+                if shutdown_signal().await.is_ok() {
+                    std::process::exit(130);
+                }
+            });
+
+            // Targets run side by side so each can watch its own backend
+            let mut running = JoinSet::new();
+            for (settings, swagger_api) in targets_with_apis {
+                running.spawn_local(run_target(settings, swagger_api, options, stopped.clone()));
             }
 
-            Ok(())
-        });
+            // A failing target doesn't stop the others, so every failure is reported once they're done
+            let mut failures = vec![];
+            while let Some(result) = running.join_next().await {
+                if let Err(e) = result.context("Target panicked").and_then(|r| r) {
+                    failures.push(e);
+                }
+            }
+
+            match failures.len() {
+                0 => Ok(()),
+                1 => Err(failures.remove(0)),
+                count => {
+                    for failure in &failures {
+                        report_error(failure, options.message_format);
+                    }
+                    Err(anyhow::anyhow!("{} targets failed", count))
+                }
+            }
+        }));
 
     result
 }
 
+// Logs go to stderr, `MORTAR_LOG` takes env_logger filters (e.g. `debug`) over -v/-q
+fn init_logging(verbose: u8, quiet: u8) {
+    let level = match (verbose, quiet) {
+        (0, 0) => log::LevelFilter::Info,
+        (0, 1) => log::LevelFilter::Warn,
+        (0, _) => log::LevelFilter::Error,
+        (1, _) => log::LevelFilter::Debug,
+        _ => log::LevelFilter::Trace,
+    };
+
+    let mut builder = env_logger::Builder::new();
+    builder.format(|buf, record| match record.level() {
+        log::Level::Info => writeln!(buf, "{}", record.args()),
+        level => writeln!(buf, "{}: {}", level, record.args()),
+    });
+
+    match std::env::var("MORTAR_LOG") {
+        Ok(filters) => builder.parse_filters(&filters),
+        // Dependencies only log when something is wrong
+        Err(_) => builder
+            .filter_level(log::LevelFilter::Warn)
+            .filter_module("mortar", level),
+    };
+
+    builder.init();
+}
+
+fn show_config(config_file: Option<&Path>, overrides: &SettingsOverrides) -> anyhow::Result<()> {
+    let configs = Settings::load_target_configs(config_file, overrides)
+        .context("Failed to create settings")?;
+
+    for (name, config) in configs {
+        let settings: Settings = config
+            .clone()
+            .try_deserialize()
+            .with_context(|| format!("Invalid settings for {}", name))?;
+
+        println!("# {}", name);
+        for (key, value, origin) in settings.describe(&config)? {
+            if !value.is_null() {
+                println!("{} = {} # from {}", key, value, origin);
+            }
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+// Resolves on Ctrl+C, or SIGTERM where there is one
+async fn shutdown_signal() -> anyhow::Result<()> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut terminate = signal(SignalKind::terminate())?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result?,
+            _ = terminate.recv() => {}
+        }
+    }
+
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c().await?;
+
+    Ok(())
+}
+
+async fn run_target(
+    settings: Settings,
+    swagger_api: Rc<SwaggerApi>,
+    options: RunOptions,
+    mut stopped: watch::Receiver<bool>,
+) -> anyhow::Result<()> {
+    let level = if settings.debug {
+        log::Level::Info
+    } else {
+        log::Level::Debug
+    };
+    log::log!(level, "Settings for {}: {:?}", settings.display_name(), &settings);
+
+    if settings.is_file_only() {
+        return emit(&swagger_api, &settings, options).await;
+    }
+
+    let mut last_build_id: Option<String> = None;
+
+    loop {
+        tokio::select! {
+            result = block_on_matching_build_id(&mut last_build_id, &swagger_api, &settings) => result?,
+            Ok(_) = stopped.wait_for(|stopped| *stopped) => break,
+        }
+
+        log::info!("Running emit for {}", settings.display_name());
+        let result = emit(&swagger_api, &settings, options).await;
+
+        if !options.watch {
+            return result;
+        }
+
+        // A broken swagger.json shouldn't end watching, the next restart of the backend may well fix it
+        if let Err(e) = result {
+            report_error(&e, options.message_format);
+            log::info!("Still watching {}", settings.display_name());
+        }
+
+        if *stopped.borrow() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+// Reports an error that doesn't end mortar
+fn report_error(error: &anyhow::Error, message_format: MessageFormat) {
+    match message_format {
+        MessageFormat::Human => log::error!("{:?}", error),
+        MessageFormat::Json => {
+            for diagnostic in diagnostics::from_error(error) {
+                diagnostic.print_json();
+            }
+        }
+    }
+}
+
+
+// Generates a target once, summarising the warnings raised along the way
+async fn emit(
+    swagger_api: &SwaggerApi,
+    settings: &Settings,
+    options: RunOptions,
+) -> anyhow::Result<()> {
+    let name = settings.display_name();
+    let (result, warnings) = diagnostics::collect_warnings(run_emit::run_emit(
+        swagger_api,
+        settings,
+        options.keep_going,
+    ))
+    .await;
+    let result = result.context(diagnostics::TargetFailed(name.to_owned()));
+
+    // Warnings are still listed when the run fails, e.g. parse errors with --keep-going
+    match (&result, warnings.is_empty()) {
+        (Ok(()), true) => log::info!("Generated {}", name),
+        (Ok(()), false) => log::warn!(
+            "Generated {} with {}",
+            name,
+            diagnostics::summarise(&warnings)
+        ),
+        (Err(_), false) => log::warn!("{} raised {}", name, diagnostics::summarise(&warnings)),
+        (Err(_), true) => {}
+    }
+
+    let warned = !warnings.is_empty();
+    for warning in warnings {
+        match options.message_format {
+            MessageFormat::Human => log::warn!("  {}", warning),
+            MessageFormat::Json => Diagnostic {
+                target: Some(name.to_owned()),
+                ..warning
+            }
+            .print_json(),
+        }
+    }
+
+    result?;
+
+    if warned && options.deny_warnings {
+        anyhow::bail!("{} raised warnings and --deny-warnings is set", name);
+    }
+
+    Ok(())
+}
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+const LONG_POLL_TIMEOUT: Duration = Duration::from_secs(300);
 
 async fn block_on_matching_build_id(
     last_build_id: &mut Option<String>,
     swagger_api: &SwaggerApi,
     settings: &Settings,
-) {
+) -> anyhow::Result<()> {
+    let mortar_endpoint = settings
+        .mortar_endpoint
+        .as_deref()
+        .context("No mortar_endpoint or swagger_file set")?;
+
+    let mut backoff = INITIAL_BACKOFF;
+    let mut waiting = false;
+
     loop {
-        let current_build_id = swagger_api
-            .get_current_build_id(&settings.mortar_endpoint, last_build_id.as_deref())
-            .await;
+        let request = swagger_api.get_current_build_id(mortar_endpoint, last_build_id.as_deref());
+
+        // The backend holds the request until it restarts, poll again if a connection has quietly gone away
+        let current_build_id = match timeout(LONG_POLL_TIMEOUT, request).await {
+            Ok(current_build_id) => current_build_id,
+            Err(_) => {
+                log::debug!("No new build id after {:?}, polling again", LONG_POLL_TIMEOUT);
+                continue;
+            }
+        };
 
         match current_build_id {
             Ok(next_build_id) => {
+                if waiting {
+                    log::info!("Backend at {} is up", mortar_endpoint);
+                    waiting = false;
+                }
+                backoff = INITIAL_BACKOFF;
+
                 match last_build_id {
                     Some(last) if last == &next_build_id => {
                         // Should never happen, delay and re-run
-                        println!("WARN: BE returned the same id, should block");
+                        log::warn!("BE returned the same id, should block");
                         sleep(Duration::from_millis(1000)).await;
                         continue;
                     }
                     _ => {
-                        dbg!("Updating build id to {}", &next_build_id);
-                        last_build_id.insert(next_build_id);
-                        break;
+                        log::debug!("Updating build id to {}", &next_build_id);
+                        *last_build_id = Some(next_build_id);
+                        return Ok(());
                     }
                 }
             }
             Err(err) => {
-                eprintln!("Failed to contact saffron backend build-id endpoint. Is your BE running? Is saffron up to date?. Error:\n{:?}", err);
-                sleep(Duration::from_millis(1000)).await;
-                continue;
+                // Said once per outage rather than on every retry
+                if !waiting {
+                    log::warn!(
+                        "Waiting for the backend at {} ({:#}). Is your BE running? Is saffron up to date?",
+                        mortar_endpoint,
+                        err
+                    );
+                    waiting = true;
+                }
+
+                log::debug!("Build id request failed, retrying in {:?}: {:#}", backoff, err);
+                sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
             }
         }
     }
 }
-
-pub async fn run_emit_from_file(path: &std::path::Path, settings: &Settings) -> anyhow::Result<()> {
-    let mut string = String::new();
-    fs::File::open(path)
-        .await?
-        .read_to_string(&mut string)
-        .await?;
-
-    let swagger: swagger::Swagger =
-        serde_json::from_str(&string).expect("file should be proper JSON");
-
-    run_emit::run_emit_from_swagger(swagger, &settings).await?;
-
-    Ok(())
-}
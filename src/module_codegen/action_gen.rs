@@ -3,10 +3,15 @@ use anyhow::{anyhow, Context};
 use itertools::Itertools;
 use crate::module_codegen::anon_object_definition::{AnonymousObjectDefinition, AnonymousPropertyValue};
 use crate::module_codegen::anon_type_definition::{AnonymousTypeDefinition, TypeDefinitionProperty};
+use crate::diagnostics::{self, Diagnostic, DiagnosticCode};
+use crate::module_codegen::date_map_gen;
 use crate::module_codegen::import_tracker::ImportTracker;
+use crate::module_codegen::input_type_gen;
+use crate::module_codegen;
 use crate::module_codegen::MortarTypeOrAnon;
 use crate::module_codegen::named_type_definitions::NamedTypeDefinition;
-use crate::parser::endpoint::{EndpointType, MortarEndpoint, MortarParam};
+use crate::parser::endpoint::{ContentKind, EndpointType, MortarEndpoint, MortarParam};
+use crate::parser;
 use crate::parser::mortar_module::MortarModule;
 use crate::parser::mortar_type::MortarType;
 use crate::schema_resolver::SchemaResolver;
@@ -14,16 +19,16 @@ use crate::string_tools::{ensure_camel_case, ensure_pascal_case};
 use std::{
     fmt::Write,
 };
-use crate::module_codegen::standalone_request_gen::{create_request_object_from_params, get_request_base_name};
-use crate::settings::Settings;
+use crate::module_codegen::standalone_request_gen::{create_error_types, create_header_params, create_request_object_from_params, get_request_base_name};
+use crate::settings::{BinaryResponseType, Settings};
 
 
-fn get_mapping_command(param: &MortarParam, resolver: &SchemaResolver) -> String {
-    match &param.schema {
+fn get_mapping_command(param: &MortarParam, resolver: &SchemaResolver) -> anyhow::Result<String> {
+    let command = match &param.schema {
         MortarType::Reference(type_ref) => {
             if resolver
                 .is_type_enum(type_ref)
-                .expect("Form Data is for unexpected type")
+                .with_context(|| format!("Failed to resolve form param {}", param.name))?
             {
                 // If its a simple enum, it should be appended to prevent inserting "'EnumVariant'"
                 "'Append'"
@@ -35,9 +40,11 @@ fn get_mapping_command(param: &MortarParam, resolver: &SchemaResolver) -> String
             MortarType::FileLike => "'ArrayAppend'",
             _ => "'JSON'",
         },
+        MortarType::Object { .. } | MortarType::Map { .. } | MortarType::Tuple { .. } => "'JSON'",
         _ => "'Append'",
-    }
-    .to_owned()
+    };
+
+    Ok(command.to_owned())
 }
 
 fn make_mapping_commands(
@@ -52,7 +59,7 @@ fn make_mapping_commands(
 
         form_commands.add_property(AnonymousPropertyValue {
             name: key,
-            value: get_mapping_command(route_param, resolver),
+            value: get_mapping_command(route_param, resolver)?,
         });
     }
 
@@ -62,16 +69,18 @@ fn make_mapping_commands(
 fn make_action_request(
     imports: &mut ImportTracker,
     endpoint: &MortarEndpoint,
+    resolver: &SchemaResolver,
+    settings: &Settings,
 ) -> anyhow::Result<(AnonymousTypeDefinition, Vec<NamedTypeDefinition>)> {
     let mut object_def = AnonymousTypeDefinition::new();
 
     let mut extra_types = vec![];
 
-    let base_name = get_request_base_name(&endpoint);
+    let base_name = get_request_base_name(endpoint);
 
     let mut add_params = |params: &Vec<MortarParam>, prop_name: &str, suffix: &str, nullable_props: bool| -> anyhow::Result<()> {
         if let Some(named) = create_request_object_from_params(
-            &params,
+            params,
             imports,
             &base_name,
             suffix,
@@ -82,6 +91,7 @@ fn make_action_request(
                 optional: false,
                 nullable: false,
                 prop_type: MortarTypeOrAnon::BlackBox(named.name.clone()),
+                doc: None,
             });
             extra_types.push(named);
         }
@@ -102,6 +112,19 @@ fn make_action_request(
         true
     )?;
 
+    if let Some(named) =
+        create_header_params(&endpoint.header_params, imports, &base_name, settings)
+    {
+        object_def.add_property(TypeDefinitionProperty {
+            name: "headerParams".to_owned(),
+            optional: named.def.properties.iter().all(|p| p.optional),
+            nullable: false,
+            prop_type: MortarTypeOrAnon::BlackBox(named.name.clone()),
+            doc: None,
+        });
+        extra_types.push(named);
+    }
+
     if let Some(named) = create_request_object_from_params(
         &endpoint.form_params,
         imports,
@@ -114,34 +137,97 @@ fn make_action_request(
             optional: false,
             nullable: false,
             prop_type: MortarTypeOrAnon::BlackBox(named.name.clone()),
+            doc: None,
         });
 
+        let form_type = match endpoint.request_content_kind() {
+            Some(ContentKind::UrlEncoded) => "URLSearchParams",
+            _ => "FormData",
+        };
+
         object_def.add_property(TypeDefinitionProperty {
             name: "formTransform".to_owned(),
             optional: true,
             nullable: false,
             prop_type: MortarTypeOrAnon::BlackBox(format!(
-                "(request: {}, commands: CommandObject) => FormData",
-                &named.name
+                "(request: {}, commands: CommandObject) => {}",
+                &named.name, form_type
             )),
+            doc: None,
         });
 
         extra_types.push(named);
     }
 
     if let Some(req) = &endpoint.request {
-        imports.track_type(req.clone());
+        let prop_type = match input_type_gen::input_type_string(req, resolver, imports)? {
+            Some(input_type) => MortarTypeOrAnon::BlackBox(input_type),
+            None => {
+                imports.track_type(req.clone());
+                MortarTypeOrAnon::Type(req.clone())
+            }
+        };
+
         object_def.add_property(TypeDefinitionProperty {
             name: "request".to_owned(),
             optional: false,
             nullable: false,
-            prop_type: MortarTypeOrAnon::Type(req.clone()),
+            prop_type,
+            doc: None,
         });
     }
 
     Ok((object_def, extra_types))
 }
 
+// Request options and headers for bodies that aren't JSON, spread before the caller's own options
+// Raw bytes, possibly alongside a success without content
+fn is_binary_response(response: Option<&MortarType>) -> bool {
+    match response {
+        Some(MortarType::Binary) => true,
+        Some(MortarType::Union(types)) => matches!(types.as_slice(), [MortarType::Binary, MortarType::Void]),
+        _ => false,
+    }
+}
+
+fn make_content_options(
+    endpoint: &MortarEndpoint,
+    response: Option<&MortarType>,
+    settings: &Settings,
+) -> (Vec<String>, Vec<String>) {
+    let mut overrides = vec![];
+    let mut headers = vec![];
+
+    if let Some(content_type) = &endpoint.request_content_type {
+        match ContentKind::new(content_type) {
+            ContentKind::Json => {}
+            // Let the browser set the multipart boundary
+            ContentKind::FormData => overrides.push("contentType: null".to_owned()),
+            _ => overrides.push(format!("contentType: \"{}\"", content_type)),
+        }
+    }
+
+    if let Some(content_type) = endpoint.response_content_type() {
+        let response_type = match ContentKind::new(content_type) {
+            ContentKind::Text => Some("text"),
+            ContentKind::Binary if is_binary_response(response) => {
+                match settings.binary_response_type {
+                    BinaryResponseType::Blob => Some("blob"),
+                    BinaryResponseType::ArrayBuffer => Some("arraybuffer"),
+                }
+            }
+            _ => None,
+        };
+
+        if let Some(response_type) = response_type {
+            overrides.push(format!("responseType: \"{}\"", response_type));
+            headers.push(format!("Accept: \"{}\"", content_type));
+        }
+    }
+
+    (overrides, headers)
+}
+
 fn create_action_request_name(endpoint: &MortarEndpoint, suffix: &str) -> String {
     let mut action_request_name = endpoint.action_name.clone();
     ensure_pascal_case(&mut action_request_name);
@@ -150,162 +236,269 @@ fn create_action_request_name(endpoint: &MortarEndpoint, suffix: &str) -> String
     action_request_name
 }
 
-pub fn generate_actions_file(
-    module: MortarModule,
-    resolver: Rc<SchemaResolver>,
-    settings: &Settings
-) -> anyhow::Result<String> {
-    let mut imports = ImportTracker::new();
-    let mut file = String::with_capacity(1024 * 1024);
+fn write_action(
+    endpoint: &MortarEndpoint,
+    module_name: &str,
+    file: &mut String,
+    imports: &mut ImportTracker,
+    resolver: &SchemaResolver,
+    settings: &Settings,
+) -> anyhow::Result<()> {
+    let formatted_route = endpoint
+        .path
+        // Remove the initial slash
+        .as_str()[1..]
+        .replace("{", "${routeParams.");
+
+    let (mut action_request, mut extra_types) = make_action_request(imports, endpoint, resolver, settings)?;
+
+    let errors = create_error_types(endpoint, imports, &get_request_base_name(endpoint));
+    // The status codes as they're keyed in the errors interface
+    let error_statuses = errors.as_ref().map(|errors| {
+        (
+            errors.name.clone(),
+            errors.def.properties.iter().map(|p| &p.name).join(", "),
+        )
+    });
+    extra_types.extend(errors);
+
+    let action_type = format!("{}/{}", module_name, endpoint.action_name);
+
+    let response = endpoint.response();
+    let return_type = match response.as_ref().map(|r| {
+        imports.track_type(r.clone());
+        r.to_type_string(resolver)
+    }) {
+        None => "void".to_owned(),
+        Some(Ok(_)) if is_binary_response(response.as_ref())
+            && settings.binary_response_type == BinaryResponseType::ArrayBuffer =>
+        {
+            match response {
+                Some(MortarType::Union(_)) => "ArrayBuffer | void".to_owned(),
+                _ => "ArrayBuffer".to_owned(),
+            }
+        }
+        Some(Ok(x)) => x,
+        Some(Err(x)) => {
+            diagnostics::warn(Diagnostic::warning(
+                DiagnosticCode::AnyFallback,
+                format!("Failed to get return type of {}, typed as unknown\n{:?}", &action_type, x),
+            ));
+            "unknown".to_owned()
+        }
+    };
 
-    // TODO create an ActionTypes object that tracks all the action_types for easier use in redux.
-    // https://rrsoftware.slack.com/archives/DMZSQ9WMD/p1657096803475849
+    let (content_options, mut headers) =
+        make_content_options(endpoint, response.as_ref(), settings);
 
-    // todo drain rather than clone
-    for endpoint in module
-        .endpoints
-        .clone()
-        .into_iter()
-        .sorted_by(|a, b| a.path.cmp(&b.path))
-    {
-        let formatted_route = endpoint
-            .path
-            // Remove the initial slash
-            .as_str()[1..]
-            .replace("{", "${routeParams.");
-
-        let (mut action_request, extra_types) = make_action_request(&mut imports, &endpoint)?;
-
-        let action_type = format!("{}/{}", &module.name, endpoint.action_name);
-
-        let return_type = match endpoint.response.as_ref().map(|r| {
-            imports.track_type(r.clone());
-            r.to_type_string(&resolver)
-        }) {
-            None => "void".to_owned(),
-            Some(Ok(x)) => x,
-            Some(Err(x)) => {
-                dbg!("Failed to get return type of {}\n{:?}", &action_type, x);
-                "unknown".to_owned()
-            }
-        };
+    let response_dates = match &response {
+        Some(r) if resolver.revive_dates => {
+            date_map_gen::date_field_expression(r, resolver, imports)?
+        }
+        _ => None,
+    };
 
-        action_request.add_property(TypeDefinitionProperty {
-            name: "options".to_string(),
-            optional: true,
-            nullable: false,
-            prop_type: MortarTypeOrAnon::BlackBox(format!(
-                "Partial<ApiRequestOptions<{}, \"{}\">>",
-                &return_type, &action_type
-            )),
-        });
+    // Until reviveResponse is called the dates are still strings
+    let wire_type = match &response_dates {
+        Some(_) => {
+            imports.track_import(module_codegen::DATES_MODULE_PATH, "reviveMortarDates");
+            imports.track_import(module_codegen::DATES_MODULE_PATH, "Unrevived");
+            format!("Unrevived<{}>", return_type)
+        }
+        None => return_type.clone(),
+    };
 
-        // no more mutating
-        let action_request = NamedTypeDefinition {
-            def: action_request,
-            name: create_action_request_name(&endpoint, "ActionRequest"),
-        };
+    action_request.add_property(TypeDefinitionProperty {
+        name: "options".to_string(),
+        optional: true,
+        nullable: false,
+        prop_type: MortarTypeOrAnon::BlackBox(format!(
+            "Partial<ApiRequestOptions<{}, \"{}\">>",
+            &wire_type, &action_type
+        )),
+        doc: None,
+    });
+
+    // no more mutating
+    let action_request = NamedTypeDefinition {
+        def: action_request,
+        name: create_action_request_name(endpoint, "ActionRequest"),
+    };
 
-        for extra in extra_types {
-            if extra.is_empty() {
-                continue;
-            }
+    for extra in extra_types {
+        if extra.is_empty() {
+            continue;
+        }
+
+        extra.write_structure_to_file(file, resolver, settings)?;
+        writeln!(file, "\n")?;
+    }
+
+    if !action_request.is_empty() {
+        action_request.write_structure_to_file(file, resolver, settings)?;
+        writeln!(file, "\n")?;
+    }
+
+    write!(file, "export const {} = ", endpoint.action_name)?;
+    if let Some((name, statuses)) = &error_statuses {
+        write!(file, "withErrors<{}>([{}])(", name, statuses)?;
+    }
+    writeln!(file, "makeAction((")?;
 
-            extra.write_structure_to_file(&mut file, &resolver, settings)?;
-            writeln!(file, "\n")?;
+    if !action_request.is_empty() {
+        write!(file, "{{")?;
+        for key in action_request.def.properties.iter().map(|p| &p.name) {
+            writeln!(file, "{},", key)?;
         }
 
-        if !action_request.is_empty() {
-            action_request.write_structure_to_file(&mut file, &resolver, settings)?;
-            writeln!(file, "\n")?;
+        write!(file, "}}:{}", &action_request.name)?;
+    }
+
+    if action_request.def.properties.iter().all(|t| t.optional) {
+        // For get requests where there is no body, make sure you don't have to specify anything.
+        write!(file, " = {{ }}")?;
+    }
+
+    write!(file, ") => ")?;
+
+    let write_optional = |file: &mut String, key: &str| -> anyhow::Result<()> {
+        if action_request.contains_property(key) {
+            write!(file, "{},", key)?;
+        } else {
+            write!(file, "undefined,")?;
         }
 
-        writeln!(file, "export const {} = makeAction((", endpoint.action_name)?;
+        Ok(())
+    };
 
-        if !action_request.is_empty() {
-            write!(file, "{{")?;
-            for key in action_request.def.properties.iter().map(|p| &p.name) {
-                write!(file, "{},\n", key)?;
-            }
+    if action_request.contains_property("headerParams") {
+        headers.push("...headerParams".to_owned());
+    }
 
-            write!(file, "}}:{}", &action_request.name)?;
+    let write_options = |file: &mut String, mut overrides: Vec<String>| -> anyhow::Result<()> {
+        overrides.extend(content_options.iter().cloned());
+
+        if overrides.is_empty() && headers.is_empty() {
+            write_optional(file, "options")?;
+            return Ok(());
         }
 
-        if action_request.def.properties.iter().all(|t| t.optional) {
-            // For get requests where there is no body, make sure you don't have to specify anything.
-            write!(file, " = {{ }}")?;
+        write!(file, "{{")?;
+        for o in overrides {
+            write!(file, "{}, ", o)?;
+        }
+        write!(file, "...options")?;
+        if !headers.is_empty() {
+            // After the spread so the caller's headers are merged rather than replacing these
+            write!(file, ", headers: {{{}, ...options?.headers}}", headers.join(", "))?;
         }
+        write!(file, "}},")?;
 
-        write!(file, ") => ")?;
+        Ok(())
+    };
 
-        let write_optional = |file: &mut String, key: &str| -> anyhow::Result<()> {
-            if action_request.contains_property(key) {
-                write!(file, "{},", key)?;
+    match &endpoint.endpoint_type {
+        EndpointType::Get => {
+            writeln!(
+                file,
+                "apiGet<{}, \"{}\">(\"{}\", `{}`,",
+                &wire_type, &action_type, &action_type, formatted_route
+            )?;
+            write_optional(file, "queryParams")?;
+            write_options(file, vec![])?;
+        }
+        _ => {
+            writeln!(
+                file,
+                "api{}<{}, \"{}\">(\"{}\",`{}`,",
+                match &endpoint.endpoint_type {
+                    EndpointType::Post => "Post",
+                    EndpointType::Put => "Put",
+                    EndpointType::Delete => "Delete",
+                    _ => Err(anyhow!(
+                        "Unknown endpoint type {:?}",
+                        endpoint.endpoint_type
+                    ))?,
+                },
+                &wire_type,
+                &action_type,
+                &action_type,
+                &formatted_route
+            )?;
+
+            if action_request.contains_property("request") {
+                write!(file, "request,")?;
+            } else if action_request.contains_property("formParams") {
+                let make_form = match endpoint.request_content_kind() {
+                    Some(ContentKind::UrlEncoded) => "makeUrlEncodedData",
+                    _ => "makeFormData",
+                };
+                writeln!(file, "(formTransform || {})(formParams,", make_form)?;
+                let commands = make_mapping_commands(endpoint, resolver)?;
+                commands.write_structure_to_file(file)?;
+                write!(file, "),")?;
             } else {
                 write!(file, "undefined,")?;
             }
 
-            Ok(())
-        };
-
-        match &endpoint.endpoint_type {
-            EndpointType::Get => {
-                writeln!(
-                    file,
-                    "apiGet<{}, \"{}\">(\"{}\", `{}`,",
-                    return_type, &action_type, &action_type, formatted_route
-                )?;
-                write_optional(&mut file, "queryParams")?;
-                write_optional(&mut file, "options")?;
+            if action_request.contains_property("queryParams") {
+                // Where a delete endpoint etc make sure that query params that should have been route params are being used.
+                write_options(file, vec!["params: queryParams".to_owned()])?;
+            } else {
+                write_options(file, vec![])?;
             }
-            _ => {
-                writeln!(
-                    file,
-                    "api{}<{}, \"{}\">(\"{}\",`{}`,",
-                    match &endpoint.endpoint_type {
-                        EndpointType::Post => "Post",
-                        EndpointType::Put => "Put",
-                        EndpointType::Delete => "Delete",
-                        _ => Err(anyhow!(
-                            "Unknown endpoint type {:?}",
-                            endpoint.endpoint_type
-                        ))?,
-                    },
-                    &return_type,
-                    &action_type,
-                    &action_type,
-                    &formatted_route
-                )?;
-
-                if action_request.contains_property("request") {
-                    write!(file, "request,")?;
-                } else if action_request.contains_property("formParams") {
-                    write!(file, "(formTransform || makeFormData)(formParams,\n")?;
-                    let commands = make_mapping_commands(&endpoint, &resolver)?;
-                    commands.write_structure_to_file(&mut file)?;
-                    write!(file, "),")?;
-                } else {
-                    write!(file, "undefined,")?;
-                }
+        }
+    };
 
-                if action_request.contains_property("queryParams")
-                    && action_request.contains_property("options")
-                {
-                    // Where a delete endpoint etc make sure that query params that should have been route params are being used.
-                    write!(file, "{{params: queryParams, ...options}},")?;
-                } else if action_request.contains_property("formParams") {
-                    if action_request.contains_property("options") {
-                        write!(file, "{{contentType: null, ...options}},")?;
-                    } else {
-                        write!(file, "undefined,")?;
-                    }
-                } else {
-                    write_optional(&mut file, "options")?;
-                }
-            }
-        };
+    write!(file, "), \"{}\"", &action_type)?;
+    if let Some(dates) = &response_dates {
+        write!(
+            file,
+            ", (response: {}) => reviveMortarDates<{}>(response, {})",
+            &wire_type, &return_type, dates
+        )?;
+    }
+    write!(file, ")")?;
+    if error_statuses.is_some() {
+        write!(file, ")")?;
+    }
+    writeln!(file, ";\n")?;
+
+    Ok(())
+}
+
+// Broken endpoints are added to `errors` and left out of the file
+pub fn generate_actions_file(
+    module: MortarModule,
+    resolver: Rc<SchemaResolver>,
+    settings: &Settings,
+    errors: &mut Vec<Diagnostic>,
+) -> anyhow::Result<String> {
+    let mut imports = ImportTracker::new();
+    let mut file = String::with_capacity(1024 * 1024);
+
+    // TODO create an ActionTypes object that tracks all the action_types for easier use in redux.
+    // https://rrsoftware.slack.com/archives/DMZSQ9WMD/p1657096803475849
 
-        writeln!(file, "), \"{}\");\n", &action_type)?;
+    // todo drain rather than clone
+    for endpoint in module
+        .endpoints
+        .clone()
+        .into_iter()
+        .sorted_by(|a, b| a.path.cmp(&b.path))
+    {
+        let pointer = parser::endpoint_pointer(&endpoint.path, endpoint.endpoint_type);
+        let _location = diagnostics::enter(&pointer);
+
+        // Written on its own so a broken endpoint can be left out and reported with the rest
+        let mut action = String::new();
+        match write_action(&endpoint, &module.name, &mut action, &mut imports, &resolver, settings) {
+            Ok(()) => file.push_str(&action),
+            Err(e) => {
+                let e = diagnostics::locate(e, DiagnosticCode::InvalidEndpoint, &pointer);
+                errors.extend(diagnostics::from_error(&e));
+            }
+        }
     }
 
     let mut import_header = String::with_capacity(10 * 1024);
@@ -316,12 +509,12 @@ pub fn generate_actions_file(
 
 
 
-    let cinnamon_library: &str = settings.cinnamon_library.as_ref().map_or_else(|| "@redriver/cinnamon-mui", |f| f);
+    let cinnamon_library: &str = settings.cinnamon_library.as_deref().unwrap_or("@redriver/cinnamon-mui");
     let file = format!(
         "// Auto Generated file, do not modify
-import {{makeAction, makeFormData, CommandObject}} from \"../lib\";\nimport {{apiGet, apiPost, apiDelete, apiPut, ApiRequestOptions}} from '{}';\n{}\n\n{}\n",
+import {{makeAction, makeFormData, makeUrlEncodedData, withErrors, CommandObject}} from \"../lib\";\nimport {{apiGet, apiPost, apiDelete, apiPut, ApiRequestOptions}} from '{}';\n{}\n\n{}\n",
         cinnamon_library, import_header, file
     );
 
-    return Ok(file);
+    Ok(file)
 }
@@ -22,11 +22,15 @@ impl AnonymousObjectDefinition {
         self.properties.push(param_property);
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.properties.is_empty()
+    }
+
     pub fn write_structure_to_file(&self, file: &mut String) -> anyhow::Result<()> {
-        write!(file, "{{\n")?;
+        writeln!(file, "{{")?;
 
         for prop in &self.properties {
-            write!(file, "{}: {},\n", prop.name, prop.value)?;
+            writeln!(file, "{}: {},", prop.name, prop.value)?;
         }
 
         write!(file, "\n}}")?;
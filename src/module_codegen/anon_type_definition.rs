@@ -27,7 +27,7 @@ impl AnonymousTypeDefinition {
         resolver: &SchemaResolver,
         settings: &Settings,
     ) -> anyhow::Result<()> {
-        write!(file, "{{\n")?;
+        writeln!(file, "{{")?;
 
         for prop in &self.properties {
             prop.write_property_to_file(file, resolver, settings)?;
@@ -46,6 +46,8 @@ pub struct TypeDefinitionProperty {
     pub optional: bool,
     pub nullable: bool,
     pub prop_type: MortarTypeOrAnon,
+    // Written as a JSDoc comment above the property
+    pub doc: Option<String>,
 }
 
 impl TypeDefinitionProperty {
@@ -55,6 +57,14 @@ impl TypeDefinitionProperty {
         resolver: &SchemaResolver,
         settings: &Settings,
     ) -> anyhow::Result<()> {
+        if let Some(doc) = &self.doc {
+            writeln!(file, "/**")?;
+            for line in doc.lines() {
+                writeln!(file, " * {}", line.replace("*/", "* /"))?;
+            }
+            writeln!(file, " */")?;
+        }
+
         write!(file, "{}", self.name)?;
 
         write!(file, "{}", if self.optional { "?: " } else { ": " })?;
@@ -69,7 +79,7 @@ impl TypeDefinitionProperty {
             write!(file, " | null")?;
         }
 
-        write!(file, ";\n")?;
+        writeln!(file, ";")?;
 
         Ok(())
     }
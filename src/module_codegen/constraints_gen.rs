@@ -0,0 +1,74 @@
+use crate::parser::mortar_concrete_type::{MortarConcreteType, MortarConcreteTypeType};
+use crate::string_tools;
+use std::fmt::Write;
+
+pub fn get_constraints_name(t: &MortarConcreteType) -> String {
+    format!("{}Constraints", t.type_name)
+}
+
+pub fn has_constraints(t: &MortarConcreteType) -> bool {
+    match &t.data {
+        MortarConcreteTypeType::Obj { properties } => {
+            properties.values().any(|p| !p.constraints.is_empty())
+        }
+        MortarConcreteTypeType::Enum(_) => false,
+    }
+}
+
+// Writes the `FooDtoConstraints` object so forms can reuse the backend's validation rules
+pub fn write_constraints(concrete: &MortarConcreteType, file: &mut String) -> anyhow::Result<()> {
+    let properties = match &concrete.data {
+        MortarConcreteTypeType::Obj { properties } => properties,
+        MortarConcreteTypeType::Enum(_) => return Ok(()),
+    };
+
+    writeln!(file, "export const {} = {{", get_constraints_name(concrete))?;
+
+    for (prop, property) in properties {
+        let constraints = &property.constraints;
+        if constraints.is_empty() {
+            continue;
+        }
+
+        let mut rules = vec![];
+        if constraints.required {
+            rules.push("required: true".to_owned());
+        }
+        if let Some(minimum) = &constraints.minimum {
+            rules.push(format!("minimum: {}", minimum));
+        }
+        if let Some(maximum) = &constraints.maximum {
+            rules.push(format!("maximum: {}", maximum));
+        }
+        if let Some(min_length) = constraints.min_length {
+            rules.push(format!("minLength: {}", min_length));
+        }
+        if let Some(max_length) = constraints.max_length {
+            rules.push(format!("maxLength: {}", max_length));
+        }
+        // Left as a string as the backend's regex dialect may need adjusting before use
+        if let Some(pattern) = &constraints.pattern {
+            rules.push(format!("pattern: {}", serde_json::to_string(pattern)?));
+        }
+        if let Some(min_items) = constraints.min_items {
+            rules.push(format!("minItems: {}", min_items));
+        }
+        if let Some(max_items) = constraints.max_items {
+            rules.push(format!("maxItems: {}", max_items));
+        }
+        if constraints.email {
+            rules.push("email: true".to_owned());
+        }
+
+        writeln!(
+            file,
+            "{}: {{ {} }},",
+            string_tools::quote_property_name(prop),
+            rules.join(", ")
+        )?;
+    }
+
+    writeln!(file, "}} as const;")?;
+
+    Ok(())
+}
@@ -0,0 +1,240 @@
+use crate::module_codegen;
+use crate::module_codegen::import_tracker::ImportTracker;
+use crate::parser::mortar_concrete_type::{
+    GenericParameterInfoType, MortarConcreteType, MortarConcreteTypeType,
+};
+use crate::parser::mortar_type::MortarType;
+use crate::schema_resolver::SchemaResolver;
+use crate::string_tools;
+use anyhow::Context;
+use itertools::Itertools;
+use std::fmt::Write;
+
+pub fn get_date_map_name(t: &MortarConcreteType) -> String {
+    format!("{}Dates", t.type_name)
+}
+
+// Expression for the `DateField` describing where the dates are within a value of the given type.
+// None where the type has no dates to revive.
+pub fn date_field_expression(
+    mortar_type: &MortarType,
+    resolver: &SchemaResolver,
+    imports: &mut ImportTracker,
+) -> anyhow::Result<Option<String>> {
+    if !resolver.contains_dates(mortar_type) {
+        return Ok(None);
+    }
+
+    let expression = match mortar_type {
+        MortarType::DateTime => Some("true".to_owned()),
+        // Arrays are walked by the reviver, so share the item's field
+        MortarType::Array(inner) => date_field_expression(inner, resolver, imports)?,
+        MortarType::Reference(r) => {
+            let concrete = resolver.resolve_to_type(r)?;
+            let name = track_date_map(concrete, imports);
+
+            match &concrete.generics {
+                Some(generics) => {
+                    let mut args = Vec::with_capacity(generics.generic_arguments.len());
+                    for arg in &generics.generic_arguments {
+                        args.push(
+                            date_field_expression(arg, resolver, imports)?
+                                .unwrap_or_else(|| "undefined".to_owned()),
+                        );
+                    }
+                    Some(format!("() => {}({})", name, args.join(", ")))
+                }
+                // Thunk so self-referencing and not yet initialised maps are resolved lazily
+                None => Some(format!("() => {}", name)),
+            }
+        }
+        MortarType::Object { properties, .. } => {
+            let mut fields = vec![];
+            for (name, t) in properties {
+                if let Some(expression) = date_field_expression(t, resolver, imports)? {
+                    fields.push(format!(
+                        "{}: {}",
+                        string_tools::quote_property_name(name),
+                        expression
+                    ));
+                }
+            }
+            Some(format!("() => ({{{}}})", fields.join(", ")))
+        }
+        // Every value of a dictionary shares the same field
+        MortarType::Map { value, .. } => date_field_expression(value, resolver, imports)?
+            .map(|expression| format!("() => ({{\"*\": {}}})", expression)),
+        MortarType::Union(types) => agreed_date_field_expression(types.iter(), resolver, imports)?,
+        MortarType::Tuple { items, rest } => {
+            agreed_date_field_expression(items.iter().chain(rest.as_deref()), resolver, imports)?
+        }
+        _ => None,
+    };
+
+    Ok(expression)
+}
+
+// The reviver can't tell union members or tuple positions apart, so only revive when they agree on the fields
+fn agreed_date_field_expression<'a>(
+    types: impl Iterator<Item = &'a MortarType>,
+    resolver: &SchemaResolver,
+    imports: &mut ImportTracker,
+) -> anyhow::Result<Option<String>> {
+    let mut union_imports = ImportTracker::new();
+    let mut expressions = vec![];
+    for t in types {
+        if let Some(expression) = date_field_expression(t, resolver, &mut union_imports)? {
+            expressions.push(expression);
+        }
+    }
+
+    match expressions.into_iter().unique().collect::<Vec<_>>().as_slice() {
+        [expression] => {
+            imports.extend(union_imports);
+            Ok(Some(expression.clone()))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn generic_date_field_expression(
+    info: &GenericParameterInfoType,
+    mortar_type: &MortarType,
+    resolver: &SchemaResolver,
+    imports: &mut ImportTracker,
+) -> anyhow::Result<Option<String>> {
+    let expression = match info {
+        GenericParameterInfoType::GenericParamPosition(pos) => Some(format!("t{}", pos)),
+        GenericParameterInfoType::TerminalType(terminal_type) => {
+            date_field_expression(terminal_type, resolver, imports)?
+        }
+        GenericParameterInfoType::Generic(items) => match mortar_type {
+            MortarType::Array(inner) => match items.first() {
+                Some(item) => generic_date_field_expression(item, inner, resolver, imports)?,
+                None => None,
+            },
+            MortarType::Reference(r) => {
+                let concrete = resolver
+                    .resolve_to_type(r)
+                    .with_context(|| format!("Failed to resolve reference to a generic {:?}", r))?;
+
+                match &concrete.generics {
+                    Some(generics) if resolver.has_date_map(concrete) => {
+                        let name = track_date_map(concrete, imports);
+                        let mut args = Vec::with_capacity(items.len());
+                        for (item, arg_type) in items.iter().zip(generics.generic_arguments.iter())
+                        {
+                            args.push(
+                                generic_date_field_expression(item, arg_type, resolver, imports)?
+                                    .unwrap_or_else(|| "undefined".to_owned()),
+                            );
+                        }
+                        Some(format!("() => {}({})", name, args.join(", ")))
+                    }
+                    _ => date_field_expression(mortar_type, resolver, imports)?,
+                }
+            }
+            _ => None,
+        },
+    };
+
+    Ok(expression)
+}
+
+fn track_date_map(concrete: &MortarConcreteType, imports: &mut ImportTracker) -> String {
+    let name = get_date_map_name(concrete);
+    imports.track_import(&module_codegen::get_concrete_type_path(concrete), &name);
+    name
+}
+
+// Writes the `FooDates` map for a type, generic types get a function taking the maps of their arguments
+pub fn write_date_map(
+    concrete: &MortarConcreteType,
+    file: &mut String,
+    resolver: &SchemaResolver,
+    imports: &mut ImportTracker,
+) -> anyhow::Result<()> {
+    let properties = match &concrete.data {
+        MortarConcreteTypeType::Obj { properties } => properties,
+        MortarConcreteTypeType::Enum(_) => return Ok(()),
+    };
+
+    let mut fields = vec![];
+    for (prop, property) in properties {
+        let mortar_type = &property.mortar_type;
+        let generic_position = concrete
+            .generics
+            .as_ref()
+            .and_then(|g| g.generic_properties.get(prop));
+
+        let expression = match generic_position {
+            Some(info) => generic_date_field_expression(info, mortar_type, resolver, imports)?,
+            None => date_field_expression(mortar_type, resolver, imports)?,
+        };
+
+        if let Some(expression) = expression {
+            fields.push((prop, expression));
+        }
+    }
+
+    let name = get_date_map_name(concrete);
+    match &concrete.generics {
+        Some(generics) => {
+            imports.track_import(module_codegen::LIB_MODULE_PATH, "DateField");
+            imports.track_import(module_codegen::LIB_MODULE_PATH, "DateFieldMap");
+            let args = (0..generics.generic_arguments.len())
+                .map(|pos| format!("t{}?: DateField", pos))
+                .collect::<Vec<_>>()
+                .join(", ");
+            writeln!(file, "export const {} = ({}): DateFieldMap => ({{", name, args)?;
+        }
+        None => {
+            imports.track_import(module_codegen::LIB_MODULE_PATH, "DateFieldMap");
+            writeln!(file, "export const {}: DateFieldMap = {{", name)?;
+        }
+    }
+
+    for (prop, expression) in fields {
+        writeln!(file, "{}: {},", string_tools::quote_property_name(prop), expression)?;
+    }
+
+    match &concrete.generics {
+        Some(_) => writeln!(file, "}});")?,
+        None => writeln!(file, "}};")?,
+    }
+
+    Ok(())
+}
+
+pub fn write_dates_module(file: &mut String, date_library: Option<&str>) -> anyhow::Result<()> {
+    writeln!(file, "// Auto Generated file, do not modify")?;
+    writeln!(file, "import {{ DateField, reviveDates }} from \"./lib\";")?;
+
+    match date_library {
+        Some(library) => {
+            writeln!(file, "import type {{ MortarDate }} from \"{}\";", library)?;
+            writeln!(file, "import {{ parseMortarDate }} from \"{}\";", library)?;
+            writeln!(file, "export type {{ MortarDate }} from \"{}\";", library)?;
+            writeln!(file, "export {{ parseMortarDate }};")?;
+        }
+        None => {
+            writeln!(file, "export type MortarDate = Date;")?;
+            writeln!(
+                file,
+                "export const parseMortarDate = (value: string): MortarDate => new Date(value);"
+            )?;
+        }
+    }
+
+    writeln!(file, "\n// A response as it arrives, with its dates still ISO strings")?;
+    writeln!(
+        file,
+        "export type Unrevived<T> = T extends MortarDate ? string : T extends (infer U)[] ? Unrevived<U>[] : T extends object ? {{ [K in keyof T]: Unrevived<T[K]> }} : T;"
+    )?;
+    writeln!(
+        file,
+        "\nexport const reviveMortarDates = <T>(value: Unrevived<T>, field: DateField | undefined): T => reviveDates(value, field, parseMortarDate) as unknown as T;"
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,262 @@
+use crate::diagnostics::{self, DiagnosticCode};
+use crate::module_codegen;
+use crate::module_codegen::import_tracker::ImportTracker;
+use crate::module_codegen::named_type_definitions;
+use crate::parser::mortar_concrete_type::{MortarConcreteType, MortarConcreteTypeType, MortarProperty};
+use crate::parser::mortar_type::MortarType;
+use crate::parser::{self, MortarTypeReference};
+use crate::schema_resolver::SchemaResolver;
+use crate::settings::Settings;
+use crate::string_tools;
+use anyhow::bail;
+use std::collections::HashSet;
+use std::fmt::Write;
+
+pub fn get_default_factory_name(t: &MortarConcreteType) -> String {
+    format!("default{}", t.type_name)
+}
+
+// Generic types have no sensible value for their arguments so don't get a factory
+pub fn has_default_factory(t: &MortarConcreteType) -> bool {
+    matches!(t.data, MortarConcreteTypeType::Obj { .. }) && t.generics.is_none()
+}
+
+// Writes the `defaultFooDto()` factory, populated with the declared defaults
+pub fn write_default_factory(
+    concrete: &MortarConcreteType,
+    file: &mut String,
+    resolver: &SchemaResolver,
+    settings: &Settings,
+    imports: &mut ImportTracker,
+) -> anyhow::Result<()> {
+    let properties = match &concrete.data {
+        MortarConcreteTypeType::Obj { properties } => properties,
+        MortarConcreteTypeType::Enum(_) => return Ok(()),
+    };
+
+    writeln!(
+        file,
+        "export const {} = (): {} => ({{",
+        get_default_factory_name(concrete),
+        concrete.type_name
+    )?;
+
+    for (prop, property) in properties {
+        let expression = match &property.default {
+            Some(default) => declared_default_expression(
+                &property.mortar_type,
+                default,
+                resolver,
+                settings,
+                imports,
+            )?,
+            None if is_nullable_reference(property) => "null".to_owned(),
+            None => empty_expression(
+                &property.mortar_type,
+                &concrete.type_ref,
+                resolver,
+                settings,
+                imports,
+            )
+            .map_err(|e| {
+                let pointer = parser::property_pointer(&concrete.type_ref, prop);
+                diagnostics::locate(e, DiagnosticCode::InvalidSchema, &pointer)
+            })?,
+        };
+
+        writeln!(
+            file,
+            "{}: {},",
+            string_tools::quote_property_name(prop),
+            expression
+        )?;
+    }
+
+    writeln!(file, "}});")?;
+
+    Ok(())
+}
+
+fn declared_default_expression(
+    mortar_type: &MortarType,
+    default: &serde_json::Value,
+    resolver: &SchemaResolver,
+    settings: &Settings,
+    imports: &mut ImportTracker,
+) -> anyhow::Result<String> {
+    let expression = match (mortar_type, default) {
+        (MortarType::DateTime, serde_json::Value::String(date)) if resolver.revive_dates => {
+            imports.track_import(module_codegen::DATES_MODULE_PATH, "parseMortarDate");
+            format!("parseMortarDate({})", serde_json::to_string(date)?)
+        }
+        (MortarType::Reference(r), _) => {
+            // Unresolved references are typed as any and warned about where the type is written
+            let concrete = match resolver.resolve_to_type(r) {
+                Ok(concrete) => concrete,
+                Err(_) => return Ok(default.to_string()),
+            };
+
+            // Refer to the matching variant so the default type checks whatever the enum style
+            let variant = match &concrete.data {
+                MortarConcreteTypeType::Enum(mortar_enum) => {
+                    mortar_enum.variants.iter().find(|v| match (&v.raw_value, default) {
+                        (Some(raw), _) => raw == &default.to_string(),
+                        (None, serde_json::Value::String(key)) => &v.key == key,
+                        _ => false,
+                    })
+                }
+                MortarConcreteTypeType::Obj { .. } => None,
+            };
+
+            match variant {
+                Some(variant) => named_type_definitions::enum_reference(
+                    &concrete.type_name,
+                    variant,
+                    settings.enum_style,
+                ),
+                None => default.to_string(),
+            }
+        }
+        _ => default.to_string(),
+    };
+
+    Ok(expression)
+}
+
+// The value of a property with no declared default, e.g. an empty string or the first enum variant
+fn empty_expression(
+    mortar_type: &MortarType,
+    owner: &MortarTypeReference,
+    resolver: &SchemaResolver,
+    settings: &Settings,
+    imports: &mut ImportTracker,
+) -> anyhow::Result<String> {
+    let expression = match mortar_type {
+        MortarType::I32 | MortarType::F32 => "0".to_owned(),
+        MortarType::Str | MortarType::Uuid => "\"\"".to_owned(),
+        MortarType::DateTime if !resolver.revive_dates => "\"\"".to_owned(),
+        MortarType::Bool => "false".to_owned(),
+        MortarType::Any | MortarType::Unknown => "null".to_owned(),
+        MortarType::Void => "undefined".to_owned(),
+        MortarType::Array(_) => "[]".to_owned(),
+        MortarType::Map { .. } => "{}".to_owned(),
+        MortarType::Union(types) => match types.first() {
+            Some(first) => empty_expression(first, owner, resolver, settings, imports)?,
+            None => "null!".to_owned(),
+        },
+        MortarType::Literal(literal) => literal.0.to_string(),
+        // Open tuples start without any of their rest
+        MortarType::Tuple { items, .. } => {
+            let mut elements = vec![];
+            for t in items {
+                elements.push(empty_expression(t, owner, resolver, settings, imports)?);
+            }
+            format!("[{}]", elements.join(", "))
+        }
+        MortarType::Object { properties, .. } => {
+            let mut fields = vec![];
+            for (name, t) in properties {
+                fields.push(format!(
+                    "{}: {}",
+                    string_tools::quote_property_name(name),
+                    empty_expression(t, owner, resolver, settings, imports)?
+                ));
+            }
+            format!("{{{}}}", fields.join(", "))
+        }
+        MortarType::Reference(r) => {
+            // Unresolved references are typed as any
+            let concrete = match resolver.resolve_to_type(r) {
+                Ok(concrete) => concrete,
+                Err(_) => return Ok("null".to_owned()),
+            };
+
+            match &concrete.data {
+                MortarConcreteTypeType::Enum(mortar_enum) => match mortar_enum.variants.first() {
+                    Some(variant) => named_type_definitions::enum_reference(
+                        &concrete.type_name,
+                        variant,
+                        settings.enum_style,
+                    ),
+                    None => "null!".to_owned(),
+                },
+                // A type that leads back to this one would never stop creating itself
+                MortarConcreteTypeType::Obj { .. } if reaches(r, owner, resolver) => bail!(
+                    "{} leads back to this type so has no default, make it nullable or declare a default",
+                    concrete.type_name
+                ),
+                MortarConcreteTypeType::Obj { .. } if has_default_factory(concrete) => {
+                    let name = get_default_factory_name(concrete);
+                    imports.track_import(&module_codegen::get_concrete_type_path(concrete), &name);
+                    format!("{}()", name)
+                }
+                MortarConcreteTypeType::Obj { .. } => "null!".to_owned(),
+            }
+        }
+        // Files, binary and dates without a library to create them are left for the form to fill
+        MortarType::DateTime | MortarType::FileLike | MortarType::Binary => "null!".to_owned(),
+    };
+
+    Ok(expression)
+}
+
+// Left empty rather than creating the referenced type
+fn is_nullable_reference(property: &MortarProperty) -> bool {
+    property.nullable && matches!(property.mortar_type, MortarType::Reference(_))
+}
+
+// The types whose factories are called when creating a value of the given type
+fn factory_references<'a>(t: &'a MortarType, references: &mut Vec<&'a MortarTypeReference>) {
+    match t {
+        MortarType::Reference(r) => references.push(r),
+        MortarType::Union(types) => {
+            if let Some(first) = types.first() {
+                factory_references(first, references);
+            }
+        }
+        MortarType::Object { properties, .. } => {
+            for t in properties.values() {
+                factory_references(t, references);
+            }
+        }
+        MortarType::Tuple { items, .. } => {
+            for t in items {
+                factory_references(t, references);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Whether creating a `from` can end up creating a `target`
+fn reaches(
+    from: &MortarTypeReference,
+    target: &MortarTypeReference,
+    resolver: &SchemaResolver,
+) -> bool {
+    let mut visited = HashSet::new();
+    let mut stack = vec![from];
+
+    while let Some(current) = stack.pop() {
+        if current == target {
+            return true;
+        }
+
+        if !visited.insert(current) {
+            continue;
+        }
+
+        if let Some(MortarConcreteTypeType::Obj { properties }) =
+            resolver.schemas.get(current).map(|t| &t.data)
+        {
+            for property in properties.values() {
+                // Declared defaults and nullable references don't create anything
+                if property.default.is_none() && !is_nullable_reference(property) {
+                    factory_references(&property.mortar_type, &mut stack);
+                }
+            }
+        }
+    }
+
+    false
+}
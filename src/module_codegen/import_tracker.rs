@@ -1,9 +1,7 @@
 use itertools::Itertools;
-use anyhow::Context;
 use crate::module_codegen;
 use crate::parser::mortar_concrete_type::MortarConcreteType;
 use crate::parser::mortar_type::MortarType;
-use crate::parser::MortarTypeReference;
 use crate::schema_resolver::SchemaResolver;
 use std::{
     collections::{HashMap, HashSet},
@@ -13,23 +11,36 @@ use std::{
 #[derive(Debug)]
 pub struct ImportTracker {
     imports: HashSet<MortarType>,
+    named_imports: HashMap<String, HashSet<String>>,
 }
 
 impl ImportTracker {
     pub fn new() -> Self {
         Self {
             imports: HashSet::new(),
+            named_imports: HashMap::new(),
         }
     }
 
-    pub fn track_ref(&mut self, reference: MortarTypeReference) {
-        self.imports.insert(MortarType::Reference(reference));
-    }
-
     pub fn track_type(&mut self, reference: MortarType) {
         self.imports.insert(reference);
     }
 
+    pub fn extend(&mut self, other: ImportTracker) {
+        self.imports.extend(other.imports);
+        for (path, names) in other.named_imports {
+            self.named_imports.entry(path).or_default().extend(names);
+        }
+    }
+
+    // For generated values that live alongside a type rather than the type itself
+    pub fn track_import(&mut self, path: &str, name: &str) {
+        self.named_imports
+            .entry(path.to_owned())
+            .or_default()
+            .insert(name.to_owned());
+    }
+
     pub fn write_imports(
         &mut self,
         file: &mut String,
@@ -46,11 +57,11 @@ impl ImportTracker {
             };
 
             write!(file, "import {{")?;
-            for import in imports.into_iter().sorted_by(|a, b| a.cmp(&b)) {
+            for import in imports.into_iter().sorted_by(|a, b| a.cmp(b)) {
                 write!(file, "{},", import)?;
             }
 
-            write!(file, "}} from \"{}\";\n", path)?;
+            writeln!(file, "}} from \"{}\";", path)?;
         }
 
         Ok(())
@@ -83,14 +94,38 @@ impl ImportTracker {
             imports: &mut HashMap<String, HashSet<String>>,
         ) {
             match t {
-                MortarType::Array(arr_type) => add_type(&arr_type, resolver, imports),
+                MortarType::DateTime if resolver.revive_dates => {
+                    imports
+                        .entry(module_codegen::DATES_MODULE_PATH.to_owned())
+                        .or_default()
+                        .insert("MortarDate".to_owned());
+                }
+                MortarType::Array(arr_type) => add_type(arr_type, resolver, imports),
+                MortarType::Union(types) => {
+                    for t in types {
+                        add_type(t, resolver, imports);
+                    }
+                }
+                MortarType::Tuple { items, rest } => {
+                    for t in items.iter().chain(rest.as_deref()) {
+                        add_type(t, resolver, imports);
+                    }
+                }
+                MortarType::Object { properties, .. } => {
+                    for t in properties.values() {
+                        add_type(t, resolver, imports);
+                    }
+                }
+                MortarType::Map { key, value } => {
+                    add_type(key, resolver, imports);
+                    add_type(value, resolver, imports);
+                }
+                // Missing references are warned about where they're written as `any`
                 MortarType::Reference(ref reference) => {
                     if let Ok(concrete_type)  = resolver
                         .resolve_to_type(reference)
                     {
                         add_concrete_type(concrete_type, resolver, imports);
-                    } else {
-                        dbg!("Failed to resolve type reference {:?}. Is the type a c# built-in or generic? Maybe an issue with MortarType::from_generic", &reference);
                     }
                 }
                 _ => {}
@@ -101,6 +136,13 @@ impl ImportTracker {
             add_type(imported_type, resolver, &mut import_collection);
         }
 
+        for (path, names) in &self.named_imports {
+            import_collection
+                .entry(path.clone())
+                .or_default()
+                .extend(names.iter().cloned());
+        }
+
         import_collection
     }
 }
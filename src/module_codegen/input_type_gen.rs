@@ -0,0 +1,106 @@
+use crate::module_codegen;
+use crate::module_codegen::import_tracker::ImportTracker;
+use crate::parser::mortar_concrete_type::{MortarConcreteType, MortarConcreteTypeType};
+use crate::parser::mortar_type::MortarType;
+use crate::schema_resolver::SchemaResolver;
+use itertools::Itertools;
+use std::fmt::Write;
+
+pub fn get_input_type_name(t: &MortarConcreteType) -> String {
+    format!("{}Input", t.type_name)
+}
+
+// Types with server generated or request only fields get a separate type for requests
+pub fn has_input_type(t: &MortarConcreteType) -> bool {
+    match &t.data {
+        MortarConcreteTypeType::Obj { properties } => {
+            properties.values().any(|p| p.read_only || p.write_only)
+        }
+        MortarConcreteTypeType::Enum(_) => false,
+    }
+}
+
+// Writes `FooDtoInput`, the type without its readOnly properties and with its writeOnly properties required
+pub fn write_input_type(concrete: &MortarConcreteType, file: &mut String) -> anyhow::Result<()> {
+    let properties = match &concrete.data {
+        MortarConcreteTypeType::Obj { properties } => properties,
+        MortarConcreteTypeType::Enum(_) => return Ok(()),
+    };
+
+    let generic_params = match &concrete.generics {
+        Some(generics) => format!(
+            "<{}>",
+            (0..generics.generic_arguments.len())
+                .map(|pos| format!("T{}", pos))
+                .join(", ")
+        ),
+        None => String::new(),
+    };
+
+    let type_name = format!("{}{}", concrete.type_name, generic_params);
+
+    let names = |filter: fn(&(&String, &_)) -> bool| -> anyhow::Result<String> {
+        Ok(properties
+            .iter()
+            .filter(filter)
+            .map(|(prop, _)| serde_json::to_string(prop))
+            .collect::<Result<Vec<_>, _>>()?
+            .join(" | "))
+    };
+
+    let read_only = names(|(_, p)| p.read_only)?;
+    let write_only = names(|(_, p)| p.write_only)?;
+
+    let mut input = match read_only.is_empty() {
+        true => type_name.clone(),
+        false => format!("Omit<{}, {}>", type_name, read_only),
+    };
+
+    if !write_only.is_empty() {
+        write!(input, " & Required<Pick<{}, {}>>", type_name, write_only)?;
+    }
+
+    writeln!(
+        file,
+        "export type {}{} = {};",
+        get_input_type_name(concrete),
+        generic_params,
+        input
+    )?;
+
+    Ok(())
+}
+
+// The type to use for a request body, None where it's the same as the type itself
+pub fn input_type_string(
+    mortar_type: &MortarType,
+    resolver: &SchemaResolver,
+    imports: &mut ImportTracker,
+) -> anyhow::Result<Option<String>> {
+    let type_string = match mortar_type {
+        MortarType::Reference(r) => {
+            let concrete = resolver.resolve_to_type(r)?;
+            if !has_input_type(concrete) {
+                return Ok(None);
+            }
+
+            let name = get_input_type_name(concrete);
+            imports.track_import(&module_codegen::get_concrete_type_path(concrete), &name);
+            // Still need the generic arguments
+            imports.track_type(mortar_type.clone());
+
+            let full_name = mortar_type.to_type_string(resolver)?;
+            let generic_args = full_name
+                .strip_prefix(concrete.type_name.as_str())
+                .unwrap_or_default();
+
+            Some(format!("{}{}", name, generic_args))
+        }
+        MortarType::Array(inner) => {
+            input_type_string(inner, resolver, imports)?.map(|inner| format!("{}[]", inner))
+        }
+        _ => None,
+    };
+
+    Ok(type_string)
+}
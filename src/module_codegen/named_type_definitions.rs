@@ -1,8 +1,8 @@
 use crate::module_codegen::anon_type_definition::AnonymousTypeDefinition;
-use crate::parser::mortar_concrete_type::EnumElement;
+use crate::parser::mortar_concrete_type::{EnumElement, MortarEnum};
 use crate::schema_resolver::SchemaResolver;
 use std::fmt::Write;
-use crate::settings::Settings;
+use crate::settings::{EnumStyle, Settings};
 
 pub struct NamedTypeDefinition {
     pub name: String,
@@ -27,36 +27,10 @@ impl WriteableTypeDefinition {
 
                 def.write_structure_to_file(file, resolver, settings)?;
 
-                write!(file, ";\n")?;
+                writeln!(file, ";")?;
             }
-            NamedTypeDefinitionDefinition::Enum(variants) => {
-                write!(file, "export const {} = {{\n", self.name)?;
-
-                let mut any_variants_raw = false;
-
-                for v in variants {
-                    if let Some(ref raw) = v.raw_value {
-                        any_variants_raw = true;
-                        write!(file, "\"{}\": {},", &v.key, raw)?;
-                    } else {
-                        write!(file, "\"{}\": \"{}\",", &v.key, &v.key)?;
-                    }
-                }
-                write!(file, "}} as const;\n")?;
-
-                if any_variants_raw {
-                    write!(
-                        file,
-                        "\nexport type {} = typeof {}[keyof typeof {}];\n",
-                        self.name, self.name, self.name
-                    )?;
-                } else {
-                    write!(
-                        file,
-                        "\nexport type {} = keyof typeof {};\n",
-                        self.name, self.name
-                    )?;
-                }
+            NamedTypeDefinitionDefinition::Enum(mortar_enum) => {
+                write_enum(&self.name, mortar_enum, file, settings)?;
             }
         }
 
@@ -79,7 +53,7 @@ impl NamedTypeDefinition {
 
         self.def.write_structure_to_file(file, resolver, settings)?;
 
-        write!(file, ";\n")?;
+        writeln!(file, ";")?;
 
         Ok(())
     }
@@ -89,7 +63,156 @@ impl NamedTypeDefinition {
     }
 }
 
+// The literal of a variant as sent over the wire
+fn enum_value(variant: &EnumElement) -> String {
+    match variant.raw_value {
+        Some(ref raw) => raw.clone(),
+        None => format!("\"{}\"", &variant.key),
+    }
+}
+
+// TS enum members can't have numeric names
+fn enum_member_name(variant: &EnumElement) -> String {
+    match variant.key.starts_with(|c: char| c.is_ascii_digit()) {
+        true => format!("_{}", variant.key),
+        false => variant.key.clone(),
+    }
+}
+
+// How other generated values refer to a variant
+pub fn enum_reference(name: &str, variant: &EnumElement, style: EnumStyle) -> String {
+    match style {
+        EnumStyle::Enum | EnumStyle::ConstEnum => {
+            format!("{}[\"{}\"]", name, enum_member_name(variant))
+        }
+        EnumStyle::ConstObject | EnumStyle::Union => enum_value(variant),
+    }
+}
+
+fn write_enum(
+    name: &str,
+    mortar_enum: &MortarEnum,
+    file: &mut String,
+    settings: &Settings,
+) -> anyhow::Result<()> {
+    let MortarEnum { variants, flags } = mortar_enum;
+    let style = settings.enum_style;
+
+    let values = variants.iter().map(enum_value).collect::<Vec<_>>();
+    let member_names = variants.iter().map(enum_member_name).collect::<Vec<_>>();
+    let references = variants
+        .iter()
+        .map(|v| enum_reference(name, v, style))
+        .collect::<Vec<_>>();
+
+    let write_description = |file: &mut String, description: &Option<String>| -> anyhow::Result<()> {
+        if let Some(description) = description {
+            writeln!(file, "/** {} */", description.replace("*/", "* /"))?;
+        }
+        Ok(())
+    };
+
+    match style {
+        EnumStyle::ConstObject => {
+            writeln!(file, "export const {} = {{", name)?;
+            for (v, value) in variants.iter().zip(&values) {
+                write_description(file, &v.description)?;
+                writeln!(file, "\"{}\": {},", &v.key, value)?;
+            }
+            writeln!(file, "}} as const;")?;
+
+            if *flags {
+                // Any combination of the flags is valid so it can't be narrowed to the variants
+                write!(file, "\nexport type {} = number;\n", name)?;
+            } else if variants.iter().any(|v| v.raw_value.is_some()) {
+                write!(
+                    file,
+                    "\nexport type {} = typeof {}[keyof typeof {}];\n",
+                    name, name, name
+                )?;
+            } else {
+                write!(file, "\nexport type {} = keyof typeof {};\n", name, name)?;
+            }
+        }
+        EnumStyle::Enum | EnumStyle::ConstEnum => {
+            let keyword = match style {
+                EnumStyle::ConstEnum => "const enum",
+                _ => "enum",
+            };
+
+            writeln!(file, "export {} {} {{", keyword, name)?;
+            for ((v, value), member) in variants.iter().zip(&values).zip(&member_names) {
+                write_description(file, &v.description)?;
+                writeln!(file, "\"{}\" = {},", member, value)?;
+            }
+            writeln!(file, "}}")?;
+        }
+        EnumStyle::Union => {
+            if *flags {
+                writeln!(file, "export type {} = number;", name)?;
+            } else {
+                writeln!(file, "export type {} = {};", name, values.join(" | "))?;
+            }
+        }
+    }
+
+    write!(
+        file,
+        "\nexport const {}Labels: Record<{}, string> = {{\n",
+        name, name
+    )?;
+    for (v, reference) in variants.iter().zip(&references) {
+        let label = serde_json::to_string(&v.label)?;
+        match style {
+            EnumStyle::Enum | EnumStyle::ConstEnum => writeln!(file, "[{}]: {},", reference, label)?,
+            // Negative numbers aren't valid keys unless computed
+            EnumStyle::ConstObject | EnumStyle::Union if reference.starts_with('-') => {
+                writeln!(file, "[{}]: {},", reference, label)?
+            }
+            EnumStyle::ConstObject | EnumStyle::Union => writeln!(file, "{}: {},", reference, label)?,
+        }
+    }
+    writeln!(file, "}};")?;
+
+    if settings.enum_values || settings.enum_type_guards || style == EnumStyle::Union {
+        write!(
+            file,
+            "\nexport const {}Values: readonly {}[] = [{}];\n",
+            name,
+            name,
+            references.join(", ")
+        )?;
+    }
+
+    if settings.enum_type_guards {
+        write!(
+            file,
+            "\nexport function is{}(value: unknown): value is {} {{\nreturn ({}Values as readonly unknown[]).includes(value);\n}}\n",
+            name, name, name
+        )?;
+    }
+
+    if *flags {
+        match style {
+            EnumStyle::ConstObject => {
+                write!(file, "\nexport const {}Flags = makeFlags({});\n", name, name)?;
+            }
+            // There's no object at runtime to take the flags from, and a numeric enum's reverse
+            // mappings would be taken as flags
+            EnumStyle::Enum | EnumStyle::ConstEnum | EnumStyle::Union => {
+                write!(file, "\nexport const {}Flags = makeFlags({{", name)?;
+                for (v, value) in variants.iter().zip(&values) {
+                    write!(file, "\"{}\": {},", &v.key, value)?;
+                }
+                writeln!(file, "}});")?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
 pub enum NamedTypeDefinitionDefinition {
     Anon(AnonymousTypeDefinition),
-    Enum(Vec<EnumElement>),
+    Enum(MortarEnum),
 }
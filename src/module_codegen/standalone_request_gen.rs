@@ -4,15 +4,15 @@ use crate::module_codegen::anon_object_definition::{
 use crate::module_codegen::anon_type_definition::{
     AnonymousTypeDefinition, TypeDefinitionProperty,
 };
+use crate::module_codegen::date_map_gen;
 use crate::module_codegen::import_tracker::ImportTracker;
 use crate::module_codegen::named_type_definitions::NamedTypeDefinition;
 use crate::module_codegen::MortarTypeOrAnon;
-use crate::parser::endpoint::{EndpointType, MortarEndpoint, MortarParam};
+use crate::parser::endpoint::{MortarEndpoint, MortarParam};
 use crate::parser::mortar_module::MortarModule;
-use crate::parser::mortar_type::MortarType;
 use crate::schema_resolver::SchemaResolver;
-use crate::string_tools::{ensure_camel_case, ensure_pascal_case};
-use anyhow::{anyhow, Context};
+use crate::string_tools::{ensure_camel_case, ensure_pascal_case, quote_property_name};
+use anyhow::Context;
 use itertools::Itertools;
 use std::fmt::Write;
 use std::rc::Rc;
@@ -40,6 +40,7 @@ pub fn create_request_object_from_params(
             optional: false,
             nullable: nullable_props,
             prop_type: MortarTypeOrAnon::Type(route_param.schema.clone()),
+            doc: None,
         });
     }
 
@@ -49,6 +50,83 @@ pub fn create_request_object_from_params(
     }))
 }
 
+// Header names are kept as-is so the object can be spread straight into the request headers
+pub fn create_header_params(
+    params: &[MortarParam],
+    imports: &mut ImportTracker,
+    name_base: &str,
+    settings: &Settings,
+) -> Option<NamedTypeDefinition> {
+    let mut headers = AnonymousTypeDefinition::new();
+
+    for param in params.iter().filter(|p| {
+        !settings
+            .ignored_headers
+            .iter()
+            .any(|ignored| ignored.eq_ignore_ascii_case(&p.name))
+    }) {
+        imports.track_type(param.schema.clone());
+        headers.add_property(TypeDefinitionProperty {
+            name: quote_property_name(&param.name),
+            optional: !param.required,
+            nullable: false,
+            prop_type: MortarTypeOrAnon::Type(param.schema.clone()),
+            doc: None,
+        });
+    }
+
+    if headers.properties.is_empty() {
+        return None;
+    }
+
+    Some(NamedTypeDefinition {
+        name: format!("{}HeaderParams", name_base),
+        def: headers,
+    })
+}
+
+// Error response bodies keyed by their status code
+pub fn create_error_types(
+    endpoint: &MortarEndpoint,
+    imports: &mut ImportTracker,
+    name_base: &str,
+) -> Option<NamedTypeDefinition> {
+    let mut errors = AnonymousTypeDefinition::new();
+
+    for response in endpoint.error_responses() {
+        let prop_type = match &response.body {
+            Some(body) => {
+                imports.track_type(body.clone());
+                MortarTypeOrAnon::Type(body.clone())
+            }
+            None => MortarTypeOrAnon::BlackBox("void".to_owned()),
+        };
+
+        let name = if response.status.chars().all(|c| c.is_ascii_digit()) {
+            response.status.clone()
+        } else {
+            format!("\"{}\"", response.status)
+        };
+
+        errors.add_property(TypeDefinitionProperty {
+            name,
+            optional: false,
+            nullable: false,
+            prop_type,
+            doc: None,
+        });
+    }
+
+    if errors.properties.is_empty() {
+        return None;
+    }
+
+    Some(NamedTypeDefinition {
+        name: format!("{}Errors", name_base),
+        def: errors,
+    })
+}
+
 pub fn get_request_base_name(endpoint: &MortarEndpoint) -> String {
     let mut action_request_name = endpoint.action_name.clone();
     ensure_pascal_case(&mut action_request_name);
@@ -58,6 +136,7 @@ pub fn get_request_base_name(endpoint: &MortarEndpoint) -> String {
 fn get_request_types(
     module: MortarModule,
     imports: &mut ImportTracker,
+    settings: &Settings,
 ) -> anyhow::Result<(Vec<NamedTypeDefinition>, AnonymousObjectDefinition)> {
     let mut action_types = vec![];
 
@@ -103,11 +182,50 @@ fn get_request_types(
         )? {
             action_types.push(named);
         }
+
+        if let Some(named) =
+            create_header_params(&endpoint.header_params, imports, &base_name, settings)
+        {
+            action_types.push(named);
+        }
+
+        if let Some(named) = create_error_types(&endpoint, imports, &base_name) {
+            action_types.push(named);
+        }
     }
 
     Ok((action_types, paths))
 }
 
+// The date fields of each endpoint's response, for use with `reviveMortarDates` in a custom client
+fn get_response_dates(
+    module: &MortarModule,
+    resolver: &SchemaResolver,
+    imports: &mut ImportTracker,
+) -> anyhow::Result<AnonymousObjectDefinition> {
+    let mut response_dates = AnonymousObjectDefinition::new();
+
+    if !resolver.revive_dates {
+        return Ok(response_dates);
+    }
+
+    for endpoint in module.endpoints.iter().sorted_by(|a, b| a.path.cmp(&b.path)) {
+        let dates = match &endpoint.response() {
+            Some(r) => date_map_gen::date_field_expression(r, resolver, imports)?,
+            None => None,
+        };
+
+        if let Some(dates) = dates {
+            response_dates.add_property(AnonymousPropertyValue {
+                name: get_request_base_name(endpoint),
+                value: dates,
+            });
+        }
+    }
+
+    Ok(response_dates)
+}
+
 pub fn generate_requests_file(
     module: MortarModule,
     resolver: Rc<SchemaResolver>,
@@ -116,23 +234,31 @@ pub fn generate_requests_file(
     let mut imports = ImportTracker::new();
     let mut file = String::with_capacity(1024 * 1024);
 
-    let (mut types, paths) = get_request_types(module, &mut imports)?;
+    let response_dates = get_response_dates(&module, &resolver, &mut imports)?;
+
+    let (mut types, paths) = get_request_types(module, &mut imports, settings)?;
 
     types.sort_by_cached_key(|t| t.name.clone());
 
-    write!(file, "// Auto Generated file, do not modify\n")?;
+    writeln!(file, "// Auto Generated file, do not modify")?;
     imports
         .write_imports(&mut file, &resolver, None)
         .context("Failed to generate imports")?;
 
     write!(file, "\nexport const PathFactory = (base_path: string) => (")?;
     paths.write_structure_to_file(&mut file)?;
-    write!(file, ");\n")?;
+    writeln!(file, ");")?;
+
+    if !response_dates.is_empty() {
+        write!(file, "\nexport const ResponseDates = ")?;
+        response_dates.write_structure_to_file(&mut file)?;
+        writeln!(file, ";")?;
+    }
 
     for t in types {
         t.write_structure_to_file(&mut file, &resolver, settings)?;
         writeln!(file, "\n")?;
     }
 
-    return Ok(file);
+    Ok(file)
 }
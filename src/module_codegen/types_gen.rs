@@ -1,34 +1,36 @@
+use crate::diagnostics::{self, Diagnostic, DiagnosticCode};
 use crate::module_codegen;
 use crate::module_codegen::anon_type_definition::{
     AnonymousTypeDefinition, TypeDefinitionProperty,
 };
+use crate::module_codegen::constraints_gen;
+use crate::module_codegen::date_map_gen;
+use crate::module_codegen::default_gen;
 use crate::module_codegen::import_tracker::ImportTracker;
+use crate::module_codegen::input_type_gen;
 use crate::module_codegen::named_type_definitions::{
     NamedTypeDefinitionDefinition, WriteableTypeDefinition,
 };
 use crate::module_codegen::MortarTypeOrAnon;
 use crate::parser::mortar_concrete_type::{
-    GenericParameterInfoType, MortarConcreteType, MortarConcreteTypeType,
+    GenericParameterInfoType, MortarConcreteType, MortarConcreteTypeType, MortarProperty,
 };
 use crate::parser::mortar_type::MortarType;
-use crate::parser::MortarTypeReference;
+use crate::parser::{self, MortarTypeReference};
 use crate::schema_resolver::SchemaResolver;
 use crate::settings::Settings;
-use anyhow::{anyhow, Context};
+use crate::string_tools;
+use anyhow::Context;
 use itertools::Itertools;
 use std::collections::{HashMap, HashSet};
 use std::fmt::Write;
 
 use regex::Regex;
 
-fn check_namespaces<'a>(
+fn check_namespaces(
     map: &HashMap<String, Vec<MortarConcreteType>>,
     settings: &Settings,
 ) -> anyhow::Result<()> {
-    let mut failed_namespaces = Vec::new();
-    let mut failed_types = Vec::new();
-    let mut failed_type_refs = Vec::new();
-
     if settings.banned_namespaces.is_empty() {
         return Ok(());
     }
@@ -52,37 +54,47 @@ fn check_namespaces<'a>(
         )
     })?;
 
-    for (path, types) in map.iter() {
-        if namespace_regex.is_match(path) {
-            failed_namespaces.push(path.to_owned());
-            failed_types.extend(types.iter().map(|t| t.type_name.clone()));
-            failed_type_refs.extend(types.iter().map(|t| t.type_ref.clone()));
+    let mut failed = Vec::new();
+
+    for (path, types) in map.iter().sorted_by_key(|(path, _)| *path) {
+        if !namespace_regex.is_match(path) {
+            continue;
         }
-    }
 
-    if !failed_namespaces.is_empty() {
-        let mut types_using_failed_types = Vec::new();
-        for types in map.values() {
-            for t in types.iter() {
-                if let MortarConcreteTypeType::Obj {ref properties} = t.data {
-                    for tt in properties.values() {
-                        match tt  {
-                            MortarType::Reference(ref r) if failed_type_refs.contains(r) => {
-                                types_using_failed_types.push(t.type_name.clone());
-                            },
-                            _ => {}
-                        }
-                    }
-                }
+        for banned in types.iter().sorted_by_key(|t| &t.type_name) {
+            // Anything with a property of the banned type, so it's clear what pulled it in
+            let users = map
+                .values()
+                .flatten()
+                .filter(|t| match &t.data {
+                    MortarConcreteTypeType::Obj { properties } => properties.values().any(|p| {
+                        matches!(&p.mortar_type, MortarType::Reference(r) if r == &banned.type_ref)
+                    }),
+                    _ => false,
+                })
+                .map(|t| t.type_name.as_str())
+                .sorted()
+                .dedup()
+                .join(", ");
+
+            let mut message = format!(
+                "{} is in the banned namespace {}",
+                banned.type_name, path
+            );
+            if !users.is_empty() {
+                write!(message, ", used by {}", users)?;
             }
+
+            failed.push(
+                Diagnostic::error(DiagnosticCode::BannedNamespace, message)
+                    .at(&banned.type_ref.0)
+                    .suggest("Please review mortar.toml for reasoning behind banning of namespace"),
+            );
         }
+    }
 
-        Err(anyhow!(
-            "Found banned namespaces included in api layer:\n {:#?}\nPlease review mortar.toml for reasoning behind banning of namespace. \nBanned types:\n{:#?}\nUsed by:\n{:#?}",
-            failed_namespaces,
-            failed_types,
-            types_using_failed_types
-        ))?;
+    if !failed.is_empty() {
+        Err(diagnostics::Diagnostics(failed))?;
     }
 
     Ok(())
@@ -122,17 +134,73 @@ pub fn create_type_files(
                 handled_generic_types.insert(concrete.type_name.to_owned());
             }
 
+            let _location = diagnostics::enter(&concrete.type_ref.0);
+
+            let date_map = if resolver.has_date_map(&concrete) {
+                let mut date_map = String::new();
+                date_map_gen::write_date_map(&concrete, &mut date_map, resolver, &mut imports)?;
+                Some(date_map)
+            } else {
+                None
+            };
+
+            let default_factory = if default_gen::has_default_factory(&concrete) {
+                let mut default_factory = String::new();
+                default_gen::write_default_factory(
+                    &concrete,
+                    &mut default_factory,
+                    resolver,
+                    settings,
+                    &mut imports,
+                )?;
+                Some(default_factory)
+            } else {
+                None
+            };
+
+            let constraints = if constraints_gen::has_constraints(&concrete) {
+                let mut constraints = String::new();
+                constraints_gen::write_constraints(&concrete, &mut constraints)?;
+                Some(constraints)
+            } else {
+                None
+            };
+
+            let input_type = if input_type_gen::has_input_type(&concrete) {
+                let mut input_type = String::new();
+                input_type_gen::write_input_type(&concrete, &mut input_type)?;
+                Some(input_type)
+            } else {
+                None
+            };
+
             let named_definition =
                 concrete_type_to_named_definition(concrete, &mut imports, resolver)?;
 
             named_definition.write_structure_to_file(&mut file, resolver, settings)?;
             write!(file, "\n\n")?;
+
+            if let Some(input_type) = input_type {
+                write!(file, "{}\n\n", input_type)?;
+            }
+
+            if let Some(date_map) = date_map {
+                write!(file, "{}\n\n", date_map)?;
+            }
+
+            if let Some(default_factory) = default_factory {
+                write!(file, "{}\n\n", default_factory)?;
+            }
+
+            if let Some(constraints) = constraints {
+                write!(file, "{}\n\n", constraints)?;
+            }
         }
 
         let mut import_header = String::with_capacity(10 * 1024);
 
         imports
-            .write_imports(&mut import_header, &resolver, Some(&path))
+            .write_imports(&mut import_header, resolver, Some(&path))
             .context("Failed to generate imports")?;
 
         let file = format!(
@@ -152,6 +220,7 @@ fn concrete_type_to_named_definition(
     resolver: &SchemaResolver,
 ) -> anyhow::Result<WriteableTypeDefinition> {
     let MortarConcreteType {
+        type_ref,
         mut type_name,
         data,
         generics,
@@ -159,39 +228,51 @@ fn concrete_type_to_named_definition(
     } = concrete;
 
     let def = match data {
-        MortarConcreteTypeType::Enum(variants) => NamedTypeDefinitionDefinition::Enum(variants),
+        MortarConcreteTypeType::Enum(mortar_enum) => {
+            if mortar_enum.flags {
+                imports.track_import(module_codegen::LIB_MODULE_PATH, "makeFlags");
+            }
+
+            NamedTypeDefinitionDefinition::Enum(mortar_enum)
+        }
         MortarConcreteTypeType::Obj { properties } => {
             let mut def = AnonymousTypeDefinition::new();
-            for (prop, mortar_type) in properties {
-                let mortar_type_for_track = mortar_type.clone();
-                let mut prop_type = MortarTypeOrAnon::Type(mortar_type);
-
-                if let Some(generics) = &generics {
-                    if let Some(generic_position) = generics.generic_properties.get(&prop) {
-                        let mut buffer = String::new();
-                        write_nested_generic_name(
-                            generic_position,
-                            &mut buffer,
-                            &mortar_type_for_track,
-                            resolver,
-                            imports,
-                        )?;
-                        prop_type = MortarTypeOrAnon::BlackBox(buffer)
-                    } else {
-                        // only track if not a generic prop
-                        imports.track_type(mortar_type_for_track);
-                    }
+            for (prop, property) in properties {
+                let _location = diagnostics::enter(parser::property_pointer(&type_ref, &prop));
+                let doc = property_doc(&property);
+                // Never present in responses
+                let optional = property.write_only;
+                let nullable = property.nullable;
+                let mortar_type = property.mortar_type;
+                let generic_position = generics
+                    .as_ref()
+                    .and_then(|generics| generics.generic_properties.get(&prop));
+
+                // Written here rather than with the rest of the file so warnings point at the property
+                let prop_type = if let Some(generic_position) = generic_position {
+                    let mut buffer = String::new();
+                    write_nested_generic_name(
+                        generic_position,
+                        &mut buffer,
+                        &mortar_type,
+                        resolver,
+                        imports,
+                    )?;
+                    MortarTypeOrAnon::BlackBox(buffer)
                 } else {
+                    let type_name = mortar_type.to_type_string(resolver)?;
                     // only track if not a generic prop
-                    imports.track_type(mortar_type_for_track);
-                }
+                    imports.track_type(mortar_type);
+                    MortarTypeOrAnon::BlackBox(type_name)
+                };
 
                 def.add_property(TypeDefinitionProperty {
-                    name: prop,
+                    name: string_tools::quote_property_name(&prop),
                     // Todo how to handle optional types
-                    optional: false,
-                    nullable: false,
+                    optional,
+                    nullable,
                     prop_type,
+                    doc,
                 });
             }
 
@@ -218,6 +299,25 @@ fn concrete_type_to_named_definition(
     })
 }
 
+// JSDoc for the declared default and example of a property
+fn property_doc(property: &MortarProperty) -> Option<String> {
+    let mut tags = vec![];
+
+    if let Some(default) = &property.default {
+        tags.push(format!("@default {}", default));
+    }
+
+    if let Some(example) = &property.example {
+        tags.push(format!("@example {}", example));
+    }
+
+    if tags.is_empty() {
+        None
+    } else {
+        Some(tags.join("\n"))
+    }
+}
+
 pub fn write_nested_generic_name(
     info: &GenericParameterInfoType,
     file: &mut String,
@@ -266,7 +366,7 @@ pub fn write_nested_generic_name(
             MortarType::Reference(r) => {
                 write_for_reference(r, items)?;
             }
-            MortarType::Array(_array_type) => match items.get(0) {
+            MortarType::Array(array_type) => match items.first() {
                 Some(GenericParameterInfoType::GenericParamPosition(pos)) => {
                     write!(file, "T{}[]", pos)?;
                 }
@@ -275,22 +375,33 @@ pub fn write_nested_generic_name(
                     write!(file, "{}[]", type_name)?;
                     imports.track_type(terminal_type.clone());
                 }
-                _ => {
+                // e.g. TreeNode<T0>[] for the children of a tree
+                Some(item @ GenericParameterInfoType::Generic(_)) => {
+                    write_nested_generic_name(item, file, array_type, resolver, imports)?;
+                    write!(file, "[]")?;
+                }
+                None => {
                     write!(file, "any[]")?;
-                    eprintln!(
-                        "WARN: Generic provided for non generic array. Defaulting to any[] {:?} {:?}",
-                        mortar_type.clone(),
-                        items.get(0)
-                    );
+                    diagnostics::warn(Diagnostic::warning(
+                        DiagnosticCode::AnyFallback,
+                        format!(
+                            "Generic provided for non generic array. Defaulting to any[] {:?} {:?}",
+                            mortar_type,
+                            items.first()
+                        ),
+                    ));
                 }
             },
             _ => {
                 write!(file, "any")?;
-                eprintln!(
-                    "WARN: Generic provided for non generic type. Defaulting to any {:?} {:?}",
-                    mortar_type.clone(),
-                    items.get(0)
-                );
+                diagnostics::warn(Diagnostic::warning(
+                    DiagnosticCode::AnyFallback,
+                    format!(
+                        "Generic provided for non generic type. Defaulting to any {:?} {:?}",
+                        mortar_type,
+                        items.first()
+                    ),
+                ));
             }
         },
     }
@@ -1,12 +1,22 @@
-export function makeAction<TApiAction, TActionType extends string>(
+// reviveResponse converts the dates within a response when revive_dates is set, otherwise it returns the response as is
+export function makeAction<
+  TApiAction,
+  TActionType extends string,
+  TResponse = unknown,
+  TRevived = TResponse
+>(
   apiAction: TApiAction,
-  actionType: TActionType
+  actionType: TActionType,
+  reviveResponse: (response: TResponse) => TRevived = (response) => response as unknown as TRevived
 ): TApiAction & {
   toString(): TActionType;
+  reviveResponse: (response: TResponse) => TRevived;
 } {
   (apiAction as any).toString = () => actionType;
+  (apiAction as any).reviveResponse = reviveResponse;
   return apiAction as TApiAction & {
     toString(): TActionType;
+    reviveResponse: (response: TResponse) => TRevived;
   };
 }
 
@@ -44,3 +54,114 @@ export function makeFormData<T extends {}>(
   }
   return formData;
 }
+
+export function makeUrlEncodedData<T extends {}>(
+  request: T,
+  commands: CommandObject
+): URLSearchParams {
+  const params = new URLSearchParams();
+
+  for (const [key, command] of Object.entries(commands)) {
+    const value = (request as any)[key];
+    if (value === undefined || value === null) {
+      continue;
+    }
+    switch (command) {
+      case "ArrayAppend":
+        if (Array.isArray(value)) {
+          for (const item of value) {
+            params.append(key, String(item));
+          }
+        }
+        break;
+      case "Append":
+        params.append(key, String(value));
+        break;
+      case "JSON":
+        params.append(key, JSON.stringify(value));
+        break;
+
+      default:
+        break;
+    }
+  }
+  return params;
+}
+
+// Where the dates are within a response, either a date itself or a map of the object's fields.
+// A "*" field applies to every key of a dictionary
+export type DateField = true | (() => DateFieldMap);
+export type DateFieldMap = { [key: string]: DateField | undefined };
+export function reviveDates<T>(
+  value: T,
+  field: DateField | undefined,
+  parseDate: (value: string) => unknown
+): T {
+  if (field === undefined || value === undefined || value === null) {
+    return value;
+  }
+
+  if (Array.isArray(value)) {
+    return value.map((item) => reviveDates(item, field, parseDate)) as T;
+  }
+
+  if (field === true) {
+    return (typeof value === "string" ? parseDate(value) : value) as T;
+  }
+
+  if (typeof value !== "object") {
+    return value;
+  }
+
+  const fields = field();
+  const result = value as any;
+  const keys = "*" in fields ? Object.keys(result) : Object.keys(fields);
+  for (const key of keys) {
+    if (key in result) {
+      result[key] = reviveDates(result[key], fields[key] ?? fields["*"], parseDate);
+    }
+  }
+
+  return value;
+}
+
+// The error responses of an endpoint as a union discriminated by status code
+export type ApiError<TErrors> = {
+  [TStatus in keyof TErrors]: { status: TStatus; body: TErrors[TStatus] };
+}[keyof TErrors];
+
+// Attaches the error responses of an action, isApiError narrows a failed request to one of them
+export function withErrors<TErrors>(statuses: (keyof TErrors)[]) {
+  return <TAction>(action: TAction) => {
+    (action as any).isApiError = (error: any): error is ApiError<TErrors> => {
+      const status = error?.status;
+      return (
+        status !== undefined &&
+        statuses.some(
+          (s) =>
+            s === status ||
+            s === "default" ||
+            (typeof s === "string" && s.endsWith("XX") && s[0] === String(status)[0])
+        )
+      );
+    };
+    return action as TAction & {
+      isApiError(error: unknown): error is ApiError<TErrors>;
+    };
+  };
+}
+
+// Bitmask helpers for [Flags] enums
+export function makeFlags<T extends Record<string, number>>(flags: T) {
+  return {
+    has: (value: number, flag: number) => (value & flag) === flag,
+    add: (value: number, flag: number) => value | flag,
+    remove: (value: number, flag: number) => value & ~flag,
+    toggle: (value: number, flag: number) => value ^ flag,
+    // The names of the flags set within the value
+    split: (value: number): (keyof T)[] =>
+      (Object.keys(flags) as (keyof T)[]).filter(
+        (key) => flags[key] !== 0 && (value & flags[key]) === flags[key]
+      ),
+  };
+}
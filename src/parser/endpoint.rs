@@ -1,4 +1,5 @@
 use crate::parser::mortar_type::MortarType;
+use itertools::Itertools;
 
 #[derive(Debug, Clone, Copy)]
 pub enum EndpointType {
@@ -8,6 +9,18 @@ pub enum EndpointType {
     Delete,
 }
 
+impl EndpointType {
+    // The operation's key in a swagger path item
+    pub fn method(&self) -> &'static str {
+        match self {
+            EndpointType::Get => "get",
+            EndpointType::Post => "post",
+            EndpointType::Put => "put",
+            EndpointType::Delete => "delete",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct MortarEndpoint {
     pub endpoint_type: EndpointType,
@@ -15,13 +28,106 @@ pub struct MortarEndpoint {
     pub route_params: Vec<MortarParam>,
     pub query_params: Vec<MortarParam>,
     pub form_params: Vec<MortarParam>,
+    pub header_params: Vec<MortarParam>,
+    // Browsers attach cookies themselves, these are recorded but not generated
+    pub cookie_params: Vec<MortarParam>,
     pub request: Option<MortarType>,
-    pub response: Option<MortarType>,
+    pub request_content_type: Option<String>,
+    pub responses: Vec<MortarResponse>,
     pub action_name: String,
 }
 
+impl MortarEndpoint {
+    // The body of a successful call, a union where 2xx codes return different bodies
+    pub fn response(&self) -> Option<MortarType> {
+        let successes = self.responses.iter().filter(|r| r.is_success()).collect::<Vec<_>>();
+        let mut bodies = successes
+            .iter()
+            .filter_map(|r| r.body.clone())
+            .unique()
+            .collect::<Vec<_>>();
+
+        // e.g. a 200 with the resource or a 204 without
+        if !bodies.is_empty() && successes.iter().any(|r| r.body.is_none()) {
+            bodies.push(MortarType::Void);
+        }
+
+        match bodies.len() {
+            0 => None,
+            1 => bodies.pop(),
+            _ => Some(MortarType::Union(bodies)),
+        }
+    }
+
+    pub fn error_responses(&self) -> impl Iterator<Item = &MortarResponse> {
+        self.responses.iter().filter(|r| !r.is_success())
+    }
+
+    pub fn request_content_kind(&self) -> Option<ContentKind> {
+        self.request_content_type.as_deref().map(ContentKind::new)
+    }
+
+    // The media type of a successful call, the first 2xx with content wins
+    pub fn response_content_type(&self) -> Option<&str> {
+        self.responses
+            .iter()
+            .filter(|r| r.is_success())
+            .find_map(|r| r.content_type.as_deref())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MortarResponse {
+    // Status code as written in the spec, can be a range such as 4XX or default
+    pub status: String,
+    pub content_type: Option<String>,
+    pub body: Option<MortarType>,
+}
+
+impl MortarResponse {
+    pub fn is_success(&self) -> bool {
+        self.status.starts_with('2')
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct MortarParam {
     pub name: String,
     pub schema: MortarType,
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentKind {
+    Json,
+    FormData,
+    UrlEncoded,
+    Text,
+    Binary,
+}
+
+impl ContentKind {
+    pub fn new(content_type: &str) -> Self {
+        // Ignore parameters such as charset
+        let media_type = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+
+        match media_type.as_str() {
+            "multipart/form-data" => ContentKind::FormData,
+            "application/x-www-form-urlencoded" => ContentKind::UrlEncoded,
+            "text/plain" | "text/html" => ContentKind::Text,
+            t if t == "application/json" || t.ends_with("/json") || t.ends_with("+json") => {
+                ContentKind::Json
+            }
+            _ => ContentKind::Binary,
+        }
+    }
+
+    pub fn is_form(&self) -> bool {
+        matches!(self, ContentKind::FormData | ContentKind::UrlEncoded)
+    }
 }
@@ -1,16 +1,17 @@
+use crate::diagnostics::{self, Diagnostic, DiagnosticCode};
 use crate::parser::mortar_module::MortarModule;
-use crate::schema_resolver::SchemaResolver;
 use crate::swagger::{Swagger, SwaggerEndpoint};
 use crate::swagger::{SwaggerComponents, SwaggerPath};
 use anyhow::Result;
 use anyhow::{anyhow, Context};
-use endpoint::{EndpointType, MortarEndpoint, MortarParam};
+use endpoint::{ContentKind, EndpointType, MortarEndpoint, MortarParam, MortarResponse};
 use mortar_concrete_type::{
     EnumElement, GenericParameterInfoType, MortarConcreteType, MortarConcreteTypeType,
-    MortarGenericInfo,
+    MortarConstraints, MortarEnum, MortarGenericInfo, MortarProperty,
 };
+use crate::string_tools;
+use itertools::Itertools;
 use mortar_type::MortarType;
-use serde::de::value;
 use std::collections::{BTreeMap, HashMap};
 
 pub(crate) mod endpoint;
@@ -24,48 +25,87 @@ pub struct MortarTypeReference(pub String);
 pub struct SwaggerParser {
     pub modules: BTreeMap<String, MortarModule>,
     pub schemas: HashMap<MortarTypeReference, MortarConcreteType>,
-    pub paths: Option<HashMap<String, SwaggerPath>>,
+    // Paths of each source, keyed by the source's name
+    pub paths: Option<Vec<(String, HashMap<String, SwaggerPath>)>>,
     pub components: SwaggerComponents,
+    // Which source each action group came from
+    module_sources: HashMap<String, String>,
 }
 
 impl SwaggerParser {
-    pub fn new(swagger: Swagger) -> Self {
-        let Swagger {
-            paths, components, ..
-        } = swagger;
-        Self {
+    // Merges the components of every source, the paths are kept apart to tell where action groups come from
+    pub fn new(sources: Vec<(String, Swagger)>) -> Result<Self> {
+        let mut components = SwaggerComponents::default();
+        let mut paths = Vec::with_capacity(sources.len());
+
+        for (source, swagger) in sources {
+            components.merge(swagger.components, &source)?;
+            paths.push((source, swagger.paths));
+        }
+
+        components.hoist_defs();
+        Ok(Self {
             modules: BTreeMap::new(),
             schemas: HashMap::new(),
             paths: Some(paths),
             components,
-        }
+            module_sources: HashMap::new(),
+        })
     }
 
     // pub fn into_modules(self) -> Vec<MortarModule> {
     //     self.modules.into_iter().map(|(_, module)| module).collect()
     // }
 
+    // Parses every endpoint and schema, skipping broken ones so they can all be reported together. What did parse is
+    // kept even when this errors.
     pub fn parse_swagger(&mut self) -> Result<()> {
         let paths = self.paths.take().context("Paths already taken")?;
+        let mut errors = Vec::new();
         // todo make this drain
-        for (endpoint_path, path) in paths {
-            self.parse_endpoint(&endpoint_path, path.get, EndpointType::Get)?;
-            self.parse_endpoint(&endpoint_path, path.post, EndpointType::Post)?;
-            self.parse_endpoint(&endpoint_path, path.put, EndpointType::Put)?;
-            self.parse_endpoint(&endpoint_path, path.delete, EndpointType::Delete)?;
+        for (source, paths) in paths {
+            // Sorted so errors come out in the same order every run
+            for (endpoint_path, path) in paths.into_iter().sorted_by(|a, b| a.0.cmp(&b.0)) {
+                let endpoints = [
+                    (path.get, EndpointType::Get),
+                    (path.post, EndpointType::Post),
+                    (path.put, EndpointType::Put),
+                    (path.delete, EndpointType::Delete),
+                ];
+
+                for (endpoint, endpoint_type) in endpoints {
+                    let pointer = endpoint_pointer(&endpoint_path, endpoint_type);
+                    let _location = diagnostics::enter(&pointer);
+
+                    if let Err(e) = self.parse_endpoint(&source, &endpoint_path, endpoint, endpoint_type) {
+                        let e = diagnostics::locate(e, DiagnosticCode::InvalidEndpoint, &pointer);
+                        errors.extend(diagnostics::from_error(&e));
+                    }
+                }
+            }
         }
 
         let keys = self
             .components
             .schemas
-            .keys()
-            .map(|k| k.clone())
+            .keys().cloned()
             .collect::<Vec<String>>();
         for schema_fragment in keys {
             let reference: String = format!("#/components/schemas/{}", &schema_fragment);
-            let type_ref = MortarTypeReference(reference);
-            self.parse_schema(type_ref)
-                .with_context(|| format!("Failed to parse schema {}", &schema_fragment))?;
+            let type_ref = MortarTypeReference(reference.clone());
+            let _location = diagnostics::enter(&reference);
+            let result = self
+                .parse_schema(type_ref)
+                .with_context(|| format!("Failed to parse schema {}", &schema_fragment));
+
+            if let Err(e) = result {
+                let e = diagnostics::locate(e, DiagnosticCode::InvalidSchema, &reference);
+                errors.extend(diagnostics::from_error(&e));
+            }
+        }
+
+        if !errors.is_empty() {
+            Err(diagnostics::Diagnostics(errors))?;
         }
 
         Ok(())
@@ -100,59 +140,56 @@ impl SwaggerParser {
             .and_then(|v| v.as_str().map(|s| s.to_owned()))
             .ok_or(anyhow!("Type doesn't include name"))?;
 
-        let data = match subject.get("type").and_then(|v| v.as_str()) {
+        let data = match mortar_type::schema_type(subject) {
             Some("object") => {
                 let props = subject.get("properties");
 
                 let mut properties = BTreeMap::new();
 
+                let required = subject
+                    .get("required")
+                    .and_then(|v| v.as_array())
+                    .map(|v| v.iter().filter_map(|r| r.as_str()).collect::<Vec<_>>())
+                    .unwrap_or_default();
+
                 if let Some(props) = props {
                     for (prop_name, opts) in props
                         .as_object()
                         .with_context(|| format!("properties is not a map - {}", &type_ref.0))?
                     {
-                        // todo should map out the nullable pop
-                        let type_name = MortarType::from_json(opts);
-                        properties.insert(prop_name.clone(), type_name);
+                        let _location = diagnostics::enter(property_pointer(&type_ref, prop_name));
+                        let property = MortarProperty {
+                            mortar_type: MortarType::from_json(opts)
+                                .with_context(|| format!("Failed to parse property {}", prop_name))?,
+                            nullable: mortar_type::is_nullable(opts),
+                            default: opts.get("default").cloned(),
+                            // 3.1 replaces example with a list of examples
+                            example: opts
+                                .get("example")
+                                .or_else(|| opts.get("examples").and_then(|e| e.get(0)))
+                                .cloned(),
+                            constraints: MortarConstraints::from_json(
+                                opts,
+                                required.contains(&prop_name.as_str()),
+                            ),
+                            read_only: opts.get("readOnly").and_then(|v| v.as_bool()) == Some(true),
+                            write_only: opts.get("writeOnly").and_then(|v| v.as_bool())
+                                == Some(true),
+                        };
+                        properties.insert(prop_name.clone(), property);
                     }
                 }
 
                 MortarConcreteTypeType::Obj { properties }
             }
-            Some("string") => {
-                let results = subject.get("enum").and_then(|v| v.as_array()).map(|o| {
-                    o.iter()
-                        .map(|value| value.as_str().unwrap().to_owned())
-                        .map(|key| EnumElement {
-                            key,
-                            raw_value: None,
-                        })
-                        .collect::<Vec<EnumElement>>()
-                });
-
-                if let Some(values) = results {
-                    MortarConcreteTypeType::Enum(values)
-                } else {
-                    Err(anyhow!("type is not an enum {:?}", subject))?
-                }
-            }
-            Some("integer") => {
-                let results = subject.get("enum").and_then(|v| v.as_array()).map(|o| {
-                    o.iter()
-                        .map(|value| value.as_f64().unwrap())
-                        .map(|value| EnumElement {
-                            key: value.to_string(),
-                            raw_value: Some(value.to_string()),
-                        })
-                        .collect::<Vec<EnumElement>>()
-                });
-
-                if let Some(values) = results {
-                    MortarConcreteTypeType::Enum(values)
-                } else {
-                    Err(anyhow!("type is not an int enum {:?}", subject))?
-                }
-            }
+            Some("string") => MortarConcreteTypeType::Enum(
+                parse_enum(subject, root)
+                    .with_context(|| format!("type is not an enum {:?}", subject))?,
+            ),
+            Some("integer") => MortarConcreteTypeType::Enum(
+                parse_enum(subject, root)
+                    .with_context(|| format!("type is not an int enum {:?}", subject))?,
+            ),
             a => Err(anyhow!("unknown type {:?}", a))?,
         };
 
@@ -172,7 +209,8 @@ impl SwaggerParser {
                 generic_args
                     .into_iter()
                     .map(MortarType::from_generic)
-                    .collect::<Vec<MortarType>>(),
+                    .collect::<Result<Vec<MortarType>>>()
+                    .context("Invalid generic arguments")?,
             );
         }
 
@@ -181,14 +219,16 @@ impl SwaggerParser {
                 generic_args
                     .iter()
                     .map(|(prop, val)| {
-                        (prop.to_owned(), mortar_concrete_type::parse_param_info(val))
+                        mortar_concrete_type::parse_param_info(val)
+                            .map(|info| (prop.to_owned(), info))
+                            .with_context(|| format!("Invalid generic property {}", prop))
                     })
-                    .collect::<BTreeMap<String, GenericParameterInfoType>>(),
+                    .collect::<Result<BTreeMap<String, GenericParameterInfoType>>>()?,
             );
         }
 
         let generics = match (generic_arguments, generic_properties) {
-            (Some(generic_arguments), Some(generic_properties)) if generic_arguments.len() > 0 => {
+            (Some(generic_arguments), Some(generic_properties)) if !generic_arguments.is_empty() => {
                 Some(MortarGenericInfo {
                     generic_arguments,
                     generic_properties,
@@ -197,7 +237,7 @@ impl SwaggerParser {
             _ => None,
         };
 
-        let mut concrete = MortarConcreteType {
+        let concrete = MortarConcreteType {
             namespace,
             type_name,
             type_ref,
@@ -213,6 +253,7 @@ impl SwaggerParser {
 
     fn parse_endpoint(
         &mut self,
+        source: &str,
         endpoint_path: &str,
         endpoint: Option<SwaggerEndpoint>,
         endpoint_type: EndpointType,
@@ -224,63 +265,101 @@ impl SwaggerParser {
 
         let SwaggerEndpoint {
             mortar,
-            description,
-            mut fields,
-            tags,
+            description: _,
+            fields,
+            tags: _,
         } = endpoint;
 
         let mortar = mortar.ok_or(anyhow!("Endpoint doesn't have mortar extensions"))?;
 
-        let module = match self.modules.get_mut(&mortar.action_group) {
-            Some(t) => t,
-            None => {
-                let module = MortarModule {
-                    name: mortar.action_group.clone(),
-                    endpoints: Vec::new(),
-                    requests: Vec::new(),
-                    responses: Vec::new(),
-                };
+        let module_source = self
+            .module_sources
+            .entry(mortar.action_group.clone())
+            .or_insert_with(|| source.to_owned());
+        if module_source != source {
+            Err(Diagnostic::error(
+                DiagnosticCode::SourceConflict,
+                format!(
+                    "Action group {} comes from both {} and {}",
+                    &mortar.action_group, module_source, source
+                ),
+            )
+            .at(endpoint_pointer(endpoint_path, endpoint_type))
+            .suggest("Give the sources a prefix"))?;
+        }
 
-                self.modules.insert(mortar.action_group.clone(), module);
-                self.modules
-                    .get_mut(&mortar.action_group)
-                    .expect("Failed to lookup just added module")
+        let mut responses = vec![];
+        if let Some(codes) = fields.get("responses").and_then(|v| v.as_object()) {
+            for (status, response) in codes {
+                let response = self
+                    .components
+                    .resolve(response)
+                    .with_context(|| format!("Failed to resolve {} response", status))?;
+                let content = response.get("content").and_then(select_content);
+                let body = content
+                    .map(|(content_type, media)| parse_body(content_type, media))
+                    .transpose()
+                    .with_context(|| format!("Failed to parse {} response", status))?
+                    .flatten();
+
+                responses.push(MortarResponse {
+                    status: status.clone(),
+                    content_type: content.map(|(content_type, _)| content_type.to_owned()),
+                    body,
+                });
             }
-        };
-
-        let response = fields
-            .get("responses")
-            .and_then(|v| v.get("200"))
-            .and_then(|v| v.get("content"))
-            .and_then(|v| v.get("application/json"))
-            .and_then(|v| v.get("schema"))
-            .map(|v| MortarType::from_json(v));
+        }
 
-        let request = fields
+        let request_body = fields
             .get("requestBody")
+            .map(|v| self.components.resolve(v))
+            .transpose()
+            .context("Failed to resolve request body")?;
+
+        let request_content = request_body
             .and_then(|v| v.get("content"))
-            .and_then(|v| v.get("application/json"))
-            .and_then(|v| v.get("schema"))
-            .map(|v| MortarType::from_json(v));
+            .and_then(select_content);
+
+        // Form bodies are sent as individual params rather than a single request object
+        let request = match request_content {
+            Some((content_type, media)) if !ContentKind::new(content_type).is_form() => {
+                parse_body(content_type, media).context("Failed to parse request body")?
+            }
+            _ => None,
+        };
 
         let mut mortar_endpoint = MortarEndpoint {
             path: endpoint_path.to_owned(),
             endpoint_type,
-            response,
+            responses,
             request,
+            request_content_type: request_content.map(|(content_type, _)| content_type.to_owned()),
             query_params: vec![],
             route_params: vec![],
             form_params: vec![],
+            header_params: vec![],
+            cookie_params: vec![],
             action_name: mortar.action_name,
         };
 
         if let Some(parameters) = fields.get("parameters").and_then(|v| v.as_array()) {
             for param in parameters {
+                let param = self
+                    .components
+                    .resolve(param)
+                    .context("Failed to resolve parameter")?;
+
+                // Params can describe their value with a media type instead of a schema
                 let schema = param
                     .get("schema")
-                    .map(|v| MortarType::from_json(v))
-                    .ok_or(anyhow!("param doesn't have schema"))?
-                    .to_owned();
+                    .or_else(|| {
+                        param
+                            .get("content")
+                            .and_then(select_content)
+                            .and_then(|(_, media)| media.get("schema"))
+                    })
+                    .map(MortarType::from_json)
+                    .ok_or(anyhow!("param doesn't have schema"))??;
 
                 let name = param
                     .get("name")
@@ -288,7 +367,16 @@ impl SwaggerParser {
                     .ok_or(anyhow!("param doesn't have name"))?
                     .to_owned();
 
-                let mortar_param = MortarParam { name, schema };
+                let required = param
+                    .get("required")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or_default();
+
+                let mortar_param = MortarParam {
+                    name,
+                    schema,
+                    required,
+                };
 
                 match param.get("in").and_then(|v| v.as_str()) {
                     Some("query") => {
@@ -298,33 +386,164 @@ impl SwaggerParser {
                         mortar_endpoint.route_params.push(mortar_param);
                     }
                     Some("header") => {
-                        // skip content in headers (assurity)
+                        mortar_endpoint.header_params.push(mortar_param);
+                    }
+                    Some("cookie") => {
+                        mortar_endpoint.cookie_params.push(mortar_param);
                     }
                     a => Err(anyhow!("unknown param location {:?}", a))?,
                 };
             }
         }
 
-        if let Some(props) = fields
-            .get("requestBody")
-            .and_then(|v| v.get("content"))
-            .and_then(|v| v.get("multipart/form-data"))
-            .and_then(|v| v.get("schema"))
+        let form_schema = request_content
+            .filter(|(content_type, _)| ContentKind::new(content_type).is_form())
+            .and_then(|(_, media)| media.get("schema"));
+
+        if let Some(props) = form_schema
             .and_then(|v| v.get("properties"))
             .and_then(|v| v.as_object())
         {
+            let required = form_schema
+                .and_then(|v| v.get("required"))
+                .and_then(|v| v.as_array());
+
             for (name, schema) in props {
-                let schema = MortarType::from_json(schema);
+                let schema = MortarType::from_json(schema)
+                    .with_context(|| format!("Failed to parse form param {}", name))?;
 
                 mortar_endpoint.form_params.push(MortarParam {
                     name: name.to_owned(),
                     schema,
+                    required: required.is_some_and(|r| r.contains(&name.as_str().into())),
                 });
             }
         }
 
-        module.endpoints.push(mortar_endpoint);
+        // Only added once the endpoint has parsed so a broken one doesn't leave an empty module behind
+        self.modules
+            .entry(mortar.action_group.clone())
+            .or_insert_with(|| MortarModule {
+                name: mortar.action_group.clone(),
+                endpoints: Vec::new(),
+            })
+            .endpoints
+            .push(mortar_endpoint);
 
         Ok(())
     }
 }
+
+// e.g. `#/paths/~1api~1foo~1{id}/get`
+pub fn endpoint_pointer(endpoint_path: &str, endpoint_type: EndpointType) -> String {
+    format!(
+        "#/paths/{}/{}",
+        diagnostics::pointer_segment(endpoint_path),
+        endpoint_type.method()
+    )
+}
+
+// e.g. `#/components/schemas/FooDto/properties/bar`
+pub fn property_pointer(type_ref: &MortarTypeReference, property: &str) -> String {
+    format!("{}/properties/{}", type_ref.0, diagnostics::pointer_segment(property))
+}
+
+// Reads a list of per variant strings from the first extension present
+fn enum_extension(
+    subject: &serde_json::Value,
+    root: Option<&serde_json::Value>,
+    extensions: &[&str],
+    mortar_extension: &str,
+) -> Vec<Option<String>> {
+    root.and_then(|v| v.get(mortar_extension))
+        .into_iter()
+        .chain(extensions.iter().filter_map(|e| subject.get(*e)))
+        .find_map(|v| v.as_array())
+        .map(|v| v.iter().map(|s| s.as_str().map(|s| s.to_owned())).collect())
+        .unwrap_or_default()
+}
+
+fn parse_enum(subject: &serde_json::Value, root: Option<&serde_json::Value>) -> Result<MortarEnum> {
+    let values = subject
+        .get("enum")
+        .and_then(|v| v.as_array())
+        .context("Missing enum values")?;
+
+    let names = enum_extension(subject, root, &["x-enumNames", "x-enum-varnames"], "en");
+    let descriptions = enum_extension(
+        subject,
+        root,
+        &["x-enum-descriptions", "x-enumDescriptions"],
+        "ed",
+    );
+    let labels = enum_extension(subject, root, &[], "el");
+
+    let mut variants = Vec::with_capacity(values.len());
+
+    for (i, value) in values.iter().enumerate() {
+        let name = names.get(i).cloned().flatten();
+
+        let (key, raw_value) = match value {
+            // Nullable enums list null as a value in 3.1
+            serde_json::Value::Null => continue,
+            serde_json::Value::String(value) => match name {
+                // The wire value differs from the name e.g. `in_progress` named `InProgress`
+                Some(name) if &name != value => (name, Some(serde_json::to_string(value)?)),
+                _ => (value.clone(), None),
+            },
+            serde_json::Value::Number(value) => {
+                (name.unwrap_or_else(|| value.to_string()), Some(value.to_string()))
+            }
+            _ => Err(anyhow!("Unexpected enum value {:?}", value))?,
+        };
+
+        let label = labels
+            .get(i)
+            .cloned()
+            .flatten()
+            .unwrap_or_else(|| string_tools::humanise(&key));
+
+        variants.push(EnumElement {
+            key,
+            raw_value,
+            label,
+            description: descriptions.get(i).cloned().flatten(),
+        });
+    }
+
+    let flags = root
+        .and_then(|v| v.get("ef"))
+        .or_else(|| subject.get("x-enumFlags"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    Ok(MortarEnum { variants, flags })
+}
+
+// Picks the media type to generate against when an endpoint offers several, preferring JSON
+fn select_content(content: &serde_json::Value) -> Option<(&str, &serde_json::Value)> {
+    let content = content.as_object()?;
+
+    content
+        .get_key_value("application/json")
+        .or_else(|| content.iter().find(|(k, _)| ContentKind::new(k) == ContentKind::Json))
+        .or_else(|| content.iter().next())
+        .map(|(k, v)| (k.as_str(), v))
+}
+
+fn parse_body(content_type: &str, media: &serde_json::Value) -> Result<Option<MortarType>> {
+    let schema = media.get("schema");
+
+    let body = match ContentKind::new(content_type) {
+        ContentKind::Json | ContentKind::FormData | ContentKind::UrlEncoded => {
+            schema.map(MortarType::from_json).transpose()?
+        }
+        ContentKind::Text if schema.and_then(|s| s.get("format")) == Some(&"binary".into()) => {
+            Some(MortarType::Binary)
+        }
+        ContentKind::Text => Some(MortarType::Str),
+        ContentKind::Binary => Some(MortarType::Binary),
+    };
+
+    Ok(body)
+}
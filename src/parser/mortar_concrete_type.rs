@@ -6,16 +6,91 @@ use crate::parser::MortarTypeReference;
 pub struct EnumElement {
     pub key: String,
     pub raw_value: Option<String>,
+    // Display name for dropdowns etc.
+    pub label: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MortarEnum {
+    pub variants: Vec<EnumElement>,
+    // [Flags] enums where values are combined as a bitmask
+    pub flags: bool,
 }
 
 #[derive(Debug, Clone)]
 pub enum MortarConcreteTypeType {
-    Enum(Vec<EnumElement>),
+    Enum(MortarEnum),
     Obj {
-        properties: BTreeMap<String, MortarType>,
+        properties: BTreeMap<String, MortarProperty>,
     },
 }
 
+#[derive(Debug, Clone)]
+pub struct MortarProperty {
+    pub mortar_type: MortarType,
+    // Allows null with a type array or union with null in 3.1
+    pub nullable: bool,
+    pub default: Option<serde_json::Value>,
+    pub example: Option<serde_json::Value>,
+    pub constraints: MortarConstraints,
+    // Only sent by the server e.g. ids
+    pub read_only: bool,
+    // Only sent to the server e.g. passwords
+    pub write_only: bool,
+}
+
+// Validation rules of a property, e.g. from DataAnnotations
+#[derive(Debug, Clone, Default)]
+pub struct MortarConstraints {
+    pub required: bool,
+    pub minimum: Option<serde_json::Number>,
+    pub maximum: Option<serde_json::Number>,
+    pub min_length: Option<u64>,
+    pub max_length: Option<u64>,
+    pub pattern: Option<String>,
+    pub min_items: Option<u64>,
+    pub max_items: Option<u64>,
+    pub email: bool,
+}
+
+impl MortarConstraints {
+    pub fn from_json(value: &serde_json::Value, required: bool) -> Self {
+        let number = |key: &str| match value.get(key) {
+            Some(serde_json::Value::Number(n)) => Some(n.clone()),
+            _ => None,
+        };
+        let count = |key: &str| value.get(key).and_then(|v| v.as_u64());
+
+        Self {
+            required,
+            minimum: number("minimum"),
+            maximum: number("maximum"),
+            min_length: count("minLength"),
+            max_length: count("maxLength"),
+            pattern: value
+                .get("pattern")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_owned()),
+            min_items: count("minItems"),
+            max_items: count("maxItems"),
+            email: value.get("format").and_then(|v| v.as_str()) == Some("email"),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.required
+            && self.minimum.is_none()
+            && self.maximum.is_none()
+            && self.min_length.is_none()
+            && self.max_length.is_none()
+            && self.pattern.is_none()
+            && self.min_items.is_none()
+            && self.max_items.is_none()
+            && !self.email
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct MortarConcreteType {
     pub type_ref: MortarTypeReference,
@@ -42,20 +117,20 @@ pub struct MortarGenericInfo {
     pub generic_properties: BTreeMap<String, GenericParameterInfoType>,
 }
 
-pub fn parse_param_info(val: &serde_json::Value) -> GenericParameterInfoType {
+pub fn parse_param_info(val: &serde_json::Value) -> anyhow::Result<GenericParameterInfoType> {
     if let Some(v) = val.as_u64() {
-        return GenericParameterInfoType::GenericParamPosition(v as usize);
+        return Ok(GenericParameterInfoType::GenericParamPosition(v as usize));
     }
 
     if let Some(v) = val.as_str() {
-        return GenericParameterInfoType::TerminalType(MortarType::from_generic(v.to_owned()));
+        return Ok(GenericParameterInfoType::TerminalType(MortarType::from_generic(v.to_owned())?));
     }
 
     if let Some(v) = val.as_array() {
-        let items = v.iter().map(parse_param_info).collect::<Vec<_>>();
+        let items = v.iter().map(parse_param_info).collect::<anyhow::Result<Vec<_>>>()?;
 
-        return GenericParameterInfoType::Generic(items);
+        return Ok(GenericParameterInfoType::Generic(items));
     }
 
-    GenericParameterInfoType::GenericParamPosition(99)
+    Ok(GenericParameterInfoType::GenericParamPosition(99))
 }
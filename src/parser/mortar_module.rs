@@ -1,10 +1,7 @@
-use crate::parser::mortar_type::MortarType;
 use crate::parser::endpoint::MortarEndpoint;
 
 #[derive(Debug)]
 pub struct MortarModule {
     pub name: String,
     pub endpoints: Vec<MortarEndpoint>,
-    pub requests: Vec<MortarType>,
-    pub responses: Vec<MortarType>,
 }
@@ -1,6 +1,11 @@
-use anyhow::anyhow;
+use crate::diagnostics::{self, Diagnostic, DiagnosticCode};
 use crate::schema_resolver::SchemaResolver;
+use crate::string_tools;
 use crate::parser::MortarTypeReference;
+use anyhow::{anyhow, bail};
+use itertools::Itertools;
+use std::collections::{BTreeMap, BTreeSet};
+use std::hash::{Hash, Hasher};
 
 impl MortarType {
     pub fn to_type_string(&self, resolver: &SchemaResolver) -> anyhow::Result<String> {
@@ -8,10 +13,58 @@ impl MortarType {
         let type_string = match self {
             MortarType::I32 | MortarType::F32 => "number".to_owned(),
             MortarType::Any => "any".to_owned(),
+            MortarType::Unknown => "unknown".to_owned(),
+            MortarType::Void => "void".to_owned(),
             MortarType::FileLike => "File".to_owned(),
+            MortarType::Binary => "Blob".to_owned(),
             MortarType::Bool => "boolean".to_owned(),
+            MortarType::DateTime if resolver.revive_dates => "MortarDate".to_owned(),
             MortarType::Uuid | MortarType::DateTime | MortarType::Str => "string".to_owned(),
-            MortarType::Array(mt) => format!("{}[]", mt.to_type_string(resolver)?),
+            MortarType::Array(mt) => match mt.as_ref() {
+                MortarType::Union(_) => format!("({})[]", mt.to_type_string(resolver)?),
+                _ => format!("{}[]", mt.to_type_string(resolver)?),
+            },
+            MortarType::Union(types) => types
+                .iter()
+                .map(|t| t.to_type_string(resolver))
+                .collect::<anyhow::Result<Vec<_>>>()?
+                .join(" | "),
+            MortarType::Tuple { items, rest } => {
+                let mut elements = items
+                    .iter()
+                    .map(|t| t.to_type_string(resolver))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                if let Some(rest) = rest {
+                    elements.push(format!("...{}", MortarType::Array(rest.clone()).to_type_string(resolver)?));
+                }
+                format!("[{}]", elements.join(", "))
+            }
+            // JSON values are also valid TS literal types
+            MortarType::Literal(literal) => literal.0.to_string(),
+            MortarType::Map { key, value } => {
+                let value = value.to_type_string(resolver)?;
+                match key.as_ref() {
+                    // Not every variant has to be present
+                    MortarType::Reference(r) if matches!(resolver.is_type_enum(r), Ok(true)) => {
+                        format!("Partial<Record<{}, {}>>", key.to_type_string(resolver)?, value)
+                    }
+                    MortarType::I32 | MortarType::F32 => format!("Record<number, {}>", value),
+                    _ => format!("Record<string, {}>", value),
+                }
+            }
+            MortarType::Object { properties, required } => {
+                let mut object = String::from("{ ");
+                for (name, property) in properties {
+                    object.push_str(&format!(
+                        "{}{}: {}; ",
+                        string_tools::quote_property_name(name),
+                        if required.contains(name) { "" } else { "?" },
+                        property.to_type_string(resolver)?
+                    ));
+                }
+                object.push('}');
+                object
+            }
             MortarType::Reference(r) => {
                 let resolved = resolver.resolve_to_type_name(r);
 
@@ -20,7 +73,10 @@ impl MortarType {
                 if let Some(resolved) = resolved {
                     resolved
                 } else {
-                    dbg!("Unable to find schema {:?}. Is this a nested generic type? Try adding [GenerateSchema(typeof(NestedType<InnerType>))] to the class", &r);
+                    diagnostics::warn(
+                        Diagnostic::warning(DiagnosticCode::UnresolvedReference, format!("Unable to find schema {}", r.0))
+                            .suggest("Is this a nested generic type? Try adding [GenerateSchema(typeof(NestedType<InnerType>))] to the class"),
+                    );
                     "any".to_owned()
                 }
             }
@@ -35,74 +91,199 @@ impl MortarType {
         Self::Reference(MortarTypeReference(reference))
     }
 
-    pub fn from_json(value: &serde_json::Value) -> Self {
+    pub fn from_json(value: &serde_json::Value) -> anyhow::Result<Self> {
         if let Some(v) = value.get("$ref") {
-            Self::new(v.as_str().unwrap().to_owned())
-        } else {
-            if value.get("anyOf").is_some() {
-                // TODO - allow for string | integer
-                return Self::Any;
+            let reference = v.as_str().ok_or_else(|| anyhow!("$ref isn't a string"))?;
+            return Ok(Self::new(reference.to_owned()));
+        }
+
+        // Nullable values are a union with null in 3.1 e.g. `anyOf: [{$ref}, {type: null}]`
+        if let Some(members) = value
+            .get("anyOf")
+            .or_else(|| value.get("oneOf"))
+            .and_then(|x| x.as_array())
+        {
+            return Ok(Self::union_of(
+                members
+                    .iter()
+                    .filter(|m| !is_null_schema(m))
+                    .map(Self::from_json)
+                    .collect::<anyhow::Result<_>>()?,
+            ));
+        }
+
+        // 3.1 type arrays e.g. `type: ["string", "null"]`
+        if let Some(types) = value.get("type").and_then(|x| x.as_array()) {
+            return Ok(Self::union_of(
+                types
+                    .iter()
+                    .filter_map(|t| t.as_str())
+                    .filter(|t| *t != "null")
+                    .map(|t| {
+                        let mut single = value.clone();
+                        single["type"] = t.into();
+                        Self::from_json(&single)
+                    })
+                    .collect::<anyhow::Result<_>>()?,
+            ));
+        }
+
+        if let Some(constant) = value.get("const") {
+            return Ok(Self::Literal(MortarLiteral(constant.clone())));
+        }
+
+        // Inline enums e.g. a discriminator with a single value
+        if let Some(values) = value.get("enum").and_then(|x| x.as_array()) {
+            if !values.is_empty() {
+                return Ok(Self::union_of(
+                    values
+                        .iter()
+                        // null isn't a literal type, a 3.0 nullable enum lists it alongside its values
+                        .filter(|v| !v.is_null())
+                        .map(|v| Self::Literal(MortarLiteral(v.clone())))
+                        .collect(),
+                ));
             }
-            match (
-                value.get("type").and_then(|x| x.as_str()),
-                value.get("format").and_then(|x| x.as_str()),
-            ) {
-                (Some("date-time"), _) => Self::DateTime,
-                (_, Some("int32") | Some("int64")) | (Some("integer"), _) => Self::I32,
-                (Some("boolean"), _) => Self::Bool,
-                (Some("float"), _) => Self::F32,
-                // TODO properly handle float vs double vs decimal
-                (Some("number"), _) => Self::F32,
-                (_, Some("uuid")) => Self::Uuid,
-                // binary file
-                (Some("string"), Some("binary")) => Self::FileLike,
-                (Some("string"), _) => Self::Str,
-                // where we don't have any info e.g. its only typed as object in BE then give any type
-                (Some("object"), _) if value.get("additionalProperties").is_none() => Self::Any,
-                (Some("array"), _) => {
-                    let items = value.get("items").expect("Array doesn't specify items");
-
-                    let items = Self::from_json(items);
-
-                    Self::Array(Box::new(items))
+        }
+
+        let mortar_name = value
+            .get("x-mtr")
+            .and_then(|x| x.as_object())
+            .and_then(|x| x.get("ne"))
+            .and_then(|x| x.as_str());
+
+        let mortar_type = match (
+            value.get("type").and_then(|x| x.as_str()),
+            value.get("format").and_then(|x| x.as_str()),
+        ) {
+            (Some("date-time"), _) | (Some("string"), Some("date-time")) => Self::DateTime,
+            (_, Some("int32") | Some("int64")) | (Some("integer"), _) => Self::I32,
+            (Some("boolean"), _) => Self::Bool,
+            (Some("float"), _) => Self::F32,
+            // TODO properly handle float vs double vs decimal
+            (Some("number"), _) => Self::F32,
+            (_, Some("uuid")) => Self::Uuid,
+            // binary file
+            (Some("string"), Some("binary")) => Self::FileLike,
+            (Some("string"), _) => Self::Str,
+            // Untyped dictionaries, typed ones are read from additionalProperties below
+            (Some("object"), _)
+                if mortar_name == Some("Dictionary")
+                    && value.get("additionalProperties").is_none() =>
+            {
+                Self::Map {
+                    key: Box::new(MortarType::Str),
+                    value: Box::new(MortarType::Any),
                 }
-                _ => {
-                    if let Some(x) = value.get("additionalProperties") {
-                        if x.is_object() {
-                            return MortarType::from_json(x);
-                        }
-                    }
+            }
+            // inline shapes e.g. from minimal APIs or anonymous property objects
+            (Some("object"), _) if value.get("additionalProperties").is_none() => {
+                match value.get("properties").and_then(|x| x.as_object()) {
+                    Some(props) if !props.is_empty() => Self::Object {
+                        properties: props
+                            .iter()
+                            .map(|(name, prop)| Ok((name.clone(), Self::from_json(prop)?)))
+                            .collect::<anyhow::Result<_>>()?,
+                        required: value
+                            .get("required")
+                            .and_then(|x| x.as_array())
+                            .map(|x| x.iter().filter_map(|r| r.as_str()).map(str::to_owned).collect())
+                            .unwrap_or_default(),
+                    },
+                    // where we don't have any info e.g. its only typed as object in BE then give any type
+                    _ => Self::Any,
+                }
+            }
+            (Some("array"), _) => {
+                // 3.1 tuples list each position in prefixItems, with items for anything after
+                let prefix_items = value.get("prefixItems").and_then(|x| x.as_array());
+                let items = prefix_items
+                    .into_iter()
+                    .flatten()
+                    .map(Self::from_json)
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+
+                let rest = match value.get("items") {
+                    Some(serde_json::Value::Bool(false)) => None,
+                    Some(x) if x.is_object() => Some(Self::from_json(x)?),
+                    None if prefix_items.is_none() => bail!("Array doesn't specify items"),
+                    // Anything can follow the prefix unless items is false
+                    _ => Some(Self::Unknown),
+                };
 
-                    return match (value
-                        .get("x-mtr")
-                        .and_then(|x| x.as_object())
-                        .and_then(|x| x.get("ne"))
-                        .and_then(|x| x.as_str()))
-                    {
-                        Some("Object") => MortarType::Any,
-                        Some("Dictionary") => MortarType::Any,
-                        Some("JToken") => MortarType::Any,
-                        x => {
-                            dbg!("Unexpected schema type {:?}\n{:?}", value, x);
-                            MortarType::Any
-                        }
+                match rest {
+                    Some(rest) if items.is_empty() => Self::Array(Box::new(rest)),
+                    rest => Self::Tuple {
+                        items,
+                        rest: rest.map(Box::new),
+                    },
+                }
+            }
+            _ => {
+                if let Some(x) = value.get("additionalProperties") {
+                    // `additionalProperties: true` allows values of any type
+                    let value_type = if x.is_object() {
+                        MortarType::from_json(x)?
+                    } else {
+                        MortarType::Any
                     };
+
+                    // NSwag describes enum keyed dictionaries with x-dictionaryKey, JSON schema with propertyNames
+                    let key_type = value
+                        .get("x-dictionaryKey")
+                        .or_else(|| value.get("propertyNames"))
+                        .map(MortarType::from_json)
+                        .transpose()?
+                        .unwrap_or(MortarType::Str);
+
+                    return Ok(MortarType::Map {
+                        key: Box::new(key_type),
+                        value: Box::new(value_type),
+                    });
+                }
+
+                match mortar_name {
+                    Some("Object") => MortarType::Any,
+                    Some("Dictionary") => MortarType::Map {
+                        key: Box::new(MortarType::Str),
+                        value: Box::new(MortarType::Any),
+                    },
+                    Some("JToken") => MortarType::Any,
+                    x => {
+                        diagnostics::warn(Diagnostic::warning(
+                            DiagnosticCode::AnyFallback,
+                            format!("Unexpected schema type {:?}, typed as any\n{}", x, value),
+                        ));
+                        MortarType::Any
+                    }
                 }
             }
+        };
+
+        Ok(mortar_type)
+    }
+
+    fn union_of(types: Vec<MortarType>) -> Self {
+        let mut types = types.into_iter().unique().collect::<Vec<_>>();
+
+        match types.len() {
+            0 => Self::Any,
+            1 => types.remove(0),
+            _ => Self::Union(types),
         }
     }
 
-    pub fn from_generic(mut value: String) -> Self {
+    pub fn from_generic(mut value: String) -> anyhow::Result<Self> {
         // This is parsing it from the `SwaggerSchemaGenerator.MakeSchemaIdForType` e.g. a different format than for usual properties
         if let Some(mini) = value.strip_suffix("[]") {
-            MortarType::Array(Box::new(MortarType::from_generic(mini.to_owned())))
+            Ok(MortarType::Array(Box::new(MortarType::from_generic(mini.to_owned())?)))
         } else {
             // Remove nullable-ness. Todo encode null-ability in MortarType? Or a type that wraps it?
             value = value.replace("Nullable__", "");
-            match value
+            let generic_type = match value
                 .as_str()
                 .strip_prefix("#/components/schemas/")
-                .expect("Generic type in invalid format")
+                .ok_or_else(|| anyhow!("Generic type {:?} isn't a schema reference", value))?
             {
                 "String" => Self::Str,
                 "Boolean" => Self::Bool,
@@ -110,23 +291,91 @@ impl MortarType {
                 "DateTime" => Self::DateTime,
                 "Guid" => Self::Uuid,
                 "Decimal" | "Single" | "Double" => Self::F32,
-                _ if value.as_str().contains("Int") => Self::I32,
+                "Byte" | "SByte" | "Int16" | "UInt16" | "Int32" | "UInt32" | "Int64" | "UInt64" => {
+                    Self::I32
+                }
                 _ => MortarType::Reference(MortarTypeReference(value)),
-            }
+            };
+
+            Ok(generic_type)
         }
     }
 }
 
+fn is_null_schema(value: &serde_json::Value) -> bool {
+    value.get("type").and_then(|x| x.as_str()) == Some("null")
+}
+
+// Whether a schema allows null with a 3.1 type array or union with null. `nullable: true` in 3.0 is left out so
+// strict_or_null types those properties as it always has
+pub fn is_nullable(value: &serde_json::Value) -> bool {
+    value
+        .get("type")
+        .and_then(|x| x.as_array())
+        .is_some_and(|types| types.iter().any(|t| t == "null"))
+        || value
+            .get("anyOf")
+            .or_else(|| value.get("oneOf"))
+            .and_then(|x| x.as_array())
+            .is_some_and(|members| members.iter().any(is_null_schema))
+}
+
+// The type of a schema, ignoring null in 3.1 type arrays
+pub fn schema_type(value: &serde_json::Value) -> Option<&str> {
+    match value.get("type") {
+        Some(serde_json::Value::Array(types)) => types
+            .iter()
+            .filter_map(|t| t.as_str())
+            .find(|t| *t != "null"),
+        Some(t) => t.as_str(),
+        None => None,
+    }
+}
+
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub enum MortarType {
     I32,
     Str,
     FileLike,
+    // Raw bytes of a non JSON body
+    Binary,
     F32,
     Bool,
     Uuid,
     DateTime,
     Any,
+    // The rest of an open tuple, which the schema doesn't describe
+    Unknown,
+    // A successful response without content
+    Void,
     Array(Box<MortarType>),
     Reference(MortarTypeReference),
+    Union(Vec<MortarType>),
+    // Dictionary, keyed by a string, number or enum
+    Map {
+        key: Box<MortarType>,
+        value: Box<MortarType>,
+    },
+    // A constant value e.g. a discriminator
+    Literal(MortarLiteral),
+    // An array with a type for each position, followed by any number of `rest` if it's open
+    Tuple {
+        items: Vec<MortarType>,
+        rest: Option<Box<MortarType>>,
+    },
+    // An inline object schema that has no named component of its own
+    Object {
+        properties: BTreeMap<String, MortarType>,
+        required: BTreeSet<String>,
+    },
+}
+
+// serde_json::Value can't be hashed, so hash literals by their JSON
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MortarLiteral(pub serde_json::Value);
+
+impl Hash for MortarLiteral {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_string().hash(state);
+    }
 }
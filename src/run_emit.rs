@@ -1,23 +1,35 @@
 use anyhow::{anyhow, Context};
-use std::fs::ReadDir;
-use std::path::{Path, PathBuf};
-use std::str::FromStr;
-use std::{collections::HashMap, rc::Rc};
+use std::path::Path;
+use std::rc::Rc;
 
+use crate::diagnostics;
 use crate::swagger::Swagger;
 use crate::{
-    formatter, module_codegen, parser::SwaggerParser, settings::Settings, swagger::SwaggerApi,
+    formatter, parser::SwaggerParser, settings::Settings, swagger::SwaggerApi,
 };
 
+use crate::module_codegen::date_map_gen::write_dates_module;
 use crate::module_codegen::{action_gen, standalone_request_gen, types_gen};
 use crate::schema_resolver::SchemaResolver;
 use tokio::fs::{create_dir_all, File};
 use tokio::io::AsyncWriteExt;
 
-pub async fn run_emit_from_swagger(swagger: Swagger, settings: &Settings) -> anyhow::Result<()> {
-    let mut parser = SwaggerParser::new(swagger);
-
-    parser.parse_swagger().context("Failed to parse swagger")?;
+// Generates one tree from every source of a target, sharing lib.ts and the dates module. With `keep_going` whatever
+// parsed is still generated before the parse errors are returned. Endpoints that fail to generate, e.g. by referring to
+// a schema that didn't parse, are left out and returned with them.
+pub async fn run_emit_from_swagger(
+    sources: Vec<(String, Swagger)>,
+    settings: &Settings,
+    keep_going: bool,
+) -> anyhow::Result<()> {
+    let mut parser = SwaggerParser::new(sources).context("Failed to merge swagger sources")?;
+
+    let parsed = parser.parse_swagger().context("Failed to parse swagger");
+    let mut errors = match parsed {
+        Err(e) if !keep_going => return Err(e),
+        Err(e) => diagnostics::from_error(&e),
+        Ok(()) => vec![],
+    };
 
     let SwaggerParser {
         modules, schemas, ..
@@ -25,7 +37,9 @@ pub async fn run_emit_from_swagger(swagger: Swagger, settings: &Settings) -> any
 
     let schemas_to_generate = schemas.values().cloned().collect::<Vec<_>>();
 
-    let resolver = Rc::new(SchemaResolver::new(schemas));
+    let resolver = Rc::new(
+        SchemaResolver::new(schemas, settings.revive_dates).context("Failed to resolve schemas")?,
+    );
 
     let formatter = get_formatter(settings);
 
@@ -37,15 +51,22 @@ pub async fn run_emit_from_swagger(swagger: Swagger, settings: &Settings) -> any
 
     let _ = tokio::fs::remove_dir_all(&output_root).await;
     create_dir_all(&output_root).await?;
-    add_mortar_lib(&output_root).await?;
+    add_mortar_lib(output_root).await?;
+
+    if settings.revive_dates {
+        add_dates_module(output_root, settings, formatter.as_ref()).await?;
+    }
 
     let module_root = output_root.join("endpoints");
     create_dir_all(&module_root).await?;
-    for (path, module) in modules.into_iter() {
+    for (path, module) in modules
+        .into_iter()
+        .filter(|(path, _)| settings.includes_action_group(path))
+    {
         let bad_code = if settings.skip_endpoint_generation {
             standalone_request_gen::generate_requests_file(module, resolver.clone(), settings)?
         } else {
-            action_gen::generate_actions_file(module, resolver.clone(), settings)?
+            action_gen::generate_actions_file(module, resolver.clone(), settings, &mut errors)?
         };
 
         let file_path = module_root.join(format!("{}.ts", path));
@@ -56,7 +77,7 @@ pub async fn run_emit_from_swagger(swagger: Swagger, settings: &Settings) -> any
 
         match result {
             Err(e) => {
-                println!("{:?}\n{}", e, bad_code);
+                log::debug!("{}", bad_code);
 
                 return Err(anyhow!("Failed to format endpoints {}\n{:?}", path, e));
             }
@@ -82,7 +103,7 @@ pub async fn run_emit_from_swagger(swagger: Swagger, settings: &Settings) -> any
 
         match result {
             Err(e) => {
-                println!("{:?}\n{}", e, source_file.source);
+                log::debug!("{}", source_file.source);
                 return Err(anyhow!(
                     "Failed to format type file {}\n{:?}",
                     &source_file.path,
@@ -96,6 +117,10 @@ pub async fn run_emit_from_swagger(swagger: Swagger, settings: &Settings) -> any
         }
     }
 
+    if !errors.is_empty() {
+        Err(diagnostics::Diagnostics(errors)).context("Failed to generate every endpoint and schema")?;
+    }
+
     Ok(())
 }
 
@@ -108,12 +133,57 @@ async fn add_mortar_lib(output_root: &Path) -> anyhow::Result<()> {
     Ok(())
 }
 
-pub async fn run_emit(swagger_api: &SwaggerApi, settings: &Settings) -> anyhow::Result<()> {
-    let swagger = swagger_api
-        .get_swagger_info(&settings.swagger_endpoint)
-        .await?;
+async fn add_dates_module(
+    output_root: &Path,
+    settings: &Settings,
+    formatter: &dyn formatter::Formatter,
+) -> anyhow::Result<()> {
+    let mut source = String::new();
+    write_dates_module(&mut source, settings.date_library.as_deref())?;
+
+    let file_path = output_root.join("dates.ts");
+    let src = formatter
+        .format(&file_path, &source)
+        .context("Failed to format the dates module")?;
+
+    let mut file = File::create(&file_path).await?;
+    file.write_all(src.as_bytes()).await?;
+
+    Ok(())
+}
+
+pub async fn run_emit(
+    swagger_api: &SwaggerApi,
+    settings: &Settings,
+    keep_going: bool,
+) -> anyhow::Result<()> {
+    let mut sources = vec![];
+
+    for source in settings.swagger_sources() {
+        let name = source.display_name();
+        let prefix = source.prefix.as_deref();
+
+        let swagger = match (&source.swagger_file, &source.swagger_endpoint) {
+            (Some(path), _) => read_swagger_file(path, prefix).await,
+            (None, Some(endpoint)) => swagger_api.get_swagger_info(endpoint, prefix).await,
+            (None, None) => Err(anyhow!("No swagger_endpoint or swagger_file set")),
+        }
+        .with_context(|| format!("Failed to load source {}", name))?;
+
+        sources.push((name, swagger));
+    }
+
+    run_emit_from_swagger(sources, settings, keep_going).await
+}
+
+async fn read_swagger_file(path: &Path, prefix: Option<&str>) -> anyhow::Result<Swagger> {
+    let string = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let document = serde_json::from_str(&string).context("File should be proper JSON")?;
 
-    run_emit_from_swagger(swagger, settings).await
+    Swagger::from_json(document, prefix)
 }
 
 fn get_formatter(settings: &Settings) -> Box<dyn formatter::Formatter> {
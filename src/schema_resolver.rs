@@ -1,16 +1,36 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use anyhow::{anyhow, Context};
+use itertools::Itertools;
 // use crate::module_codegen;
 use crate::parser::mortar_concrete_type::{MortarConcreteType, MortarConcreteTypeType};
+use crate::parser::mortar_type::MortarType;
 use crate::parser::MortarTypeReference;
 
 pub struct SchemaResolver {
     pub schemas: HashMap<MortarTypeReference, MortarConcreteType>,
+    pub revive_dates: bool,
+    // Types that contain a date-time somewhere within them, only populated when reviving dates
+    date_types: HashSet<MortarTypeReference>,
 }
 
 impl SchemaResolver {
-    pub fn new(schemas: HashMap<MortarTypeReference, MortarConcreteType>) -> SchemaResolver {
-        SchemaResolver { schemas }
+    pub fn new(
+        schemas: HashMap<MortarTypeReference, MortarConcreteType>,
+        revive_dates: bool,
+    ) -> anyhow::Result<SchemaResolver> {
+        check_generic_cycles(&schemas)?;
+
+        let date_types = if revive_dates {
+            find_date_types(&schemas)
+        } else {
+            HashSet::new()
+        };
+
+        Ok(SchemaResolver {
+            schemas,
+            revive_dates,
+            date_types,
+        })
     }
 
     pub fn resolve_to_type_name(
@@ -34,20 +54,179 @@ impl SchemaResolver {
 
     pub fn is_type_enum(&self, type_ref: &MortarTypeReference) -> anyhow::Result<bool> {
         let concrete = self.resolve_to_type(type_ref)?;
-        let is_enum = match &concrete.data {
-            MortarConcreteTypeType::Enum(_) => true,
+        let is_enum = matches!(&concrete.data, MortarConcreteTypeType::Enum(_));
+
+        Ok(is_enum)
+    }
+
+    pub fn contains_dates(&self, mortar_type: &MortarType) -> bool {
+        match mortar_type {
+            MortarType::DateTime => self.revive_dates,
+            MortarType::Array(inner) => self.contains_dates(inner),
+            MortarType::Reference(r) => self.date_types.contains(r),
+            MortarType::Union(types) => types.iter().any(|t| self.contains_dates(t)),
+            MortarType::Tuple { items, rest } => {
+                items.iter().chain(rest.as_deref()).any(|t| self.contains_dates(t))
+            }
+            MortarType::Object { properties, .. } => properties.values().any(|t| self.contains_dates(t)),
+            MortarType::Map { value, .. } => self.contains_dates(value),
             _ => false,
+        }
+    }
+
+    // Generic types share a single date map between all of their instances
+    pub fn has_date_map(&self, concrete: &MortarConcreteType) -> bool {
+        if concrete.generics.is_none() {
+            return self.date_types.contains(&concrete.type_ref);
+        }
+
+        self.date_types.iter().filter_map(|r| self.schemas.get(r)).any(|t| {
+            t.generics.is_some()
+                && t.type_name == concrete.type_name
+                && t.namespace == concrete.namespace
+        })
+    }
+}
+
+fn collect_references<'a>(t: &'a MortarType, references: &mut Vec<&'a MortarTypeReference>) {
+    match t {
+        MortarType::Reference(r) => references.push(r),
+        MortarType::Array(inner) => collect_references(inner, references),
+        MortarType::Union(types) => {
+            for t in types {
+                collect_references(t, references);
+            }
+        }
+        MortarType::Tuple { items, rest } => {
+            for t in items.iter().chain(rest.as_deref()) {
+                collect_references(t, references);
+            }
+        }
+        MortarType::Object { properties, .. } => {
+            for t in properties.values() {
+                collect_references(t, references);
+            }
+        }
+        MortarType::Map { key, value } => {
+            collect_references(key, references);
+            collect_references(value, references);
+        }
+        _ => {}
+    }
+}
+
+// Generic arguments are expanded eagerly when naming, importing and reviving a type (unlike properties,
+// which only ever refer to a type by name), so a type that is its own argument would never terminate.
+fn check_generic_cycles(
+    schemas: &HashMap<MortarTypeReference, MortarConcreteType>,
+) -> anyhow::Result<()> {
+    fn visit<'a>(
+        type_ref: &'a MortarTypeReference,
+        schemas: &'a HashMap<MortarTypeReference, MortarConcreteType>,
+        path: &mut Vec<&'a MortarTypeReference>,
+        checked: &mut HashSet<&'a MortarTypeReference>,
+    ) -> anyhow::Result<()> {
+        if checked.contains(type_ref) {
+            return Ok(());
+        }
+
+        if let Some(start) = path.iter().position(|r| *r == type_ref) {
+            let cycle = path[start..]
+                .iter()
+                .chain(std::iter::once(&type_ref))
+                .map(|r| r.0.trim_start_matches("#/components/schemas/"))
+                .collect::<Vec<_>>()
+                .join(" -> ");
+
+            return Err(anyhow!(
+                "Generic type arguments form a cycle: {}. A generic type can't be its own argument",
+                cycle
+            ));
+        }
+
+        let generics = match schemas.get(type_ref).and_then(|t| t.generics.as_ref()) {
+            Some(generics) => generics,
+            None => {
+                checked.insert(type_ref);
+                return Ok(());
+            }
         };
 
-        Ok(is_enum)
+        let mut references = vec![];
+        for arg in &generics.generic_arguments {
+            collect_references(arg, &mut references);
+        }
+
+        path.push(type_ref);
+        for reference in references {
+            visit(reference, schemas, path, checked)?;
+        }
+        path.pop();
+
+        checked.insert(type_ref);
+
+        Ok(())
     }
+
+    let mut checked = HashSet::new();
+    for type_ref in schemas.keys().sorted_by(|a, b| a.0.cmp(&b.0)) {
+        visit(type_ref, schemas, &mut vec![], &mut checked)?;
+    }
+
+    Ok(())
+}
+
+fn find_date_types(
+    schemas: &HashMap<MortarTypeReference, MortarConcreteType>,
+) -> HashSet<MortarTypeReference> {
+    fn has_dates(t: &MortarType, date_types: &HashSet<MortarTypeReference>) -> bool {
+        match t {
+            MortarType::DateTime => true,
+            MortarType::Array(inner) => has_dates(inner, date_types),
+            MortarType::Reference(r) => date_types.contains(r),
+            MortarType::Union(types) => types.iter().any(|t| has_dates(t, date_types)),
+            MortarType::Tuple { items, rest } => {
+                items.iter().chain(rest.as_deref()).any(|t| has_dates(t, date_types))
+            }
+            MortarType::Object { properties, .. } => {
+                properties.values().any(|t| has_dates(t, date_types))
+            }
+            MortarType::Map { value, .. } => has_dates(value, date_types),
+            _ => false,
+        }
+    }
+
+    let mut date_types = HashSet::new();
+
+    // Keep sweeping until nothing new is found so self and mutually referencing types terminate
+    loop {
+        let found = schemas
+            .values()
+            .filter(|t| !date_types.contains(&t.type_ref))
+            .filter(|t| match &t.data {
+                MortarConcreteTypeType::Obj { properties } => {
+                    properties.values().any(|p| has_dates(&p.mortar_type, &date_types))
+                }
+                MortarConcreteTypeType::Enum(_) => false,
+            })
+            .map(|t| t.type_ref.clone())
+            .collect::<Vec<_>>();
+
+        if found.is_empty() {
+            break;
+        }
+
+        date_types.extend(found);
+    }
+
+    date_types
 }
 
 fn map_concrete_type(t: &MortarConcreteType, resolver: &SchemaResolver) -> anyhow::Result<String> {
     let mut type_name = t.type_name.clone();
 
     if let Some(generics) = &t.generics {
-        type_name.push_str("<");
+        type_name.push('<');
 
         let len = generics.generic_arguments.len();
 
@@ -59,7 +238,7 @@ fn map_concrete_type(t: &MortarConcreteType, resolver: &SchemaResolver) -> anyho
             }
         }
 
-        type_name.push_str(">");
+        type_name.push('>');
     }
 
     // if its an enum.
@@ -1,35 +1,528 @@
-use config::ConfigError;
-use serde::Deserialize;
+use crate::string_tools;
+use anyhow::{anyhow, Context};
+use config::builder::DefaultState;
+use config::{Config, ConfigBuilder, Source, Value, ValueKind};
+use schemars::schema::{ArrayValidation, InstanceType, RootSchema, Schema, SchemaObject};
+use schemars::JsonSchema;
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize};
+use regex::{Captures, Regex};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct Settings {
+    /// Identifies a target in output when there are several
+    pub name: Option<String>,
+    /// Print the settings each target runs with
     #[serde(default)]
     pub debug: bool,
-    pub swagger_endpoint: String,
-    pub mortar_endpoint: String,
+    /// The backend's swagger.json
+    pub swagger_endpoint: Option<String>,
+    /// Blocks until the backend's build id changes, so `mortar --watch` knows when to regenerate
+    pub mortar_endpoint: Option<String>,
+    /// Generate from a swagger.json on disk rather than the running backend
+    pub swagger_file: Option<PathBuf>,
+    /// Relative directory that is cleared and regenerated on every run
     pub output_dir: String,
+    /// Generate standalone request functions instead of redux actions
     #[serde(default)]
     pub skip_endpoint_generation: bool,
+    /// Write the generated code without formatting it
     #[serde(default)]
     pub no_format: bool,
+    /// Regexes of namespaces whose types aren't generated, e.g. database entities
     #[serde(default)]
     pub banned_namespaces: Vec<String>,
+    /// Module the generated actions import their request helpers from, `@redriver/cinnamon-mui` by default
     pub cinnamon_library: Option<String>,
+    /// Type nullable properties as `T | null` as well as optional
     #[serde(default)]
     pub strict_or_null: bool,
+    /// Type date-time fields as dates and revive them from the ISO strings in responses
+    #[serde(default)]
+    pub revive_dates: bool,
+    /// Module exporting `MortarDate` and `parseMortarDate` to use instead of the built-in Date
+    pub date_library: Option<String>,
+    #[serde(default)]
+    pub binary_response_type: BinaryResponseType,
+    /// Header parameters that are supplied elsewhere (e.g. auth) and left out of requests
+    #[serde(default)]
+    pub ignored_headers: Vec<String>,
+    #[serde(default)]
+    pub enum_style: EnumStyle,
+    /// Generate `isFoo(value)` type guards for enums
+    #[serde(default)]
+    pub enum_type_guards: bool,
+    /// Generate a `FooValues` array of every variant, always on for the union style
+    #[serde(default)]
+    pub enum_values: bool,
+    /// Only generate these action groups, all of them when empty
+    #[serde(default)]
+    pub action_groups: Vec<String>,
+    /// Action groups to leave out
+    #[serde(default)]
+    pub excluded_action_groups: Vec<String>,
+    /// Several swagger documents merged into this target, instead of swagger_endpoint/swagger_file
+    #[serde(default)]
+    pub sources: Vec<SwaggerSource>,
+    /// Headers, auth, certificates, proxy and timeout for fetching the swagger.json and build id
+    #[serde(default)]
+    pub http: HttpSettings,
+}
+
+/// How the swagger.json and build id are requested, e.g. for backends behind auth or a proxy.
+/// `${VAR}` in headers, tokens, passwords and the proxy is replaced with the environment variable so secrets can stay
+/// out of mortar.toml.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct HttpSettings {
+    /// Sent with every request
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Sent as `Authorization: Bearer <token>`
+    pub bearer_token: Option<String>,
+    pub basic_auth: Option<BasicAuth>,
+    /// PEM bundle of root certificates to trust alongside the built-in ones
+    pub ca_certificate: Option<PathBuf>,
+    /// PEM file holding a client certificate and its private key
+    pub client_certificate: Option<PathBuf>,
+    /// e.g. `http://proxy.example.com:8080`, otherwise `HTTPS_PROXY`/`HTTP_PROXY` are used
+    pub proxy: Option<String>,
+    /// Seconds to wait for a response, 60 by default. The build id is long polled so waiting on a change isn't limited.
+    pub timeout_secs: Option<u64>,
+    /// Accept any certificate, only for a local backend over https with a self signed certificate
+    #[serde(default)]
+    pub danger_accept_invalid_certs: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct BasicAuth {
+    pub username: String,
+    pub password: Option<String>,
+}
+
+// Replaces `${VAR}` with the environment variable VAR, failing when it isn't set
+pub fn interpolate_env(value: &str) -> anyhow::Result<String> {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    let pattern = PATTERN.get_or_init(|| Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}").unwrap());
+    let mut missing = None;
+
+    let interpolated = pattern.replace_all(value, |captures: &Captures| {
+        let name = &captures[1];
+        std::env::var(name).unwrap_or_else(|_| {
+            missing.get_or_insert_with(|| name.to_owned());
+            String::new()
+        })
+    });
+
+    match missing {
+        Some(name) => Err(anyhow!("Environment variable {} isn't set", name)),
+        None => Ok(interpolated.into_owned()),
+    }
+}
+
+/// One swagger document making up a target
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct SwaggerSource {
+    pub swagger_endpoint: Option<String>,
+    pub swagger_file: Option<PathBuf>,
+    /// Prepended to the source's namespaces, type references and action groups so they can't collide
+    pub prefix: Option<String>,
+}
+
+impl SwaggerSource {
+    pub fn display_name(&self) -> String {
+        match (&self.prefix, &self.swagger_file, &self.swagger_endpoint) {
+            (Some(prefix), _, _) => prefix.clone(),
+            (None, Some(file), _) => file.display().to_string(),
+            (None, None, Some(endpoint)) => endpoint.clone(),
+            (None, None, None) => "<unnamed source>".to_owned(),
+        }
+    }
+}
+
+/// How enums are emitted
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EnumStyle {
+    /// `export const Foo = {...} as const` plus a type of its values
+    #[default]
+    ConstObject,
+    Enum,
+    ConstEnum,
+    /// A bare union of the values
+    Union,
 }
 
+/// How file and other non-text responses are read
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BinaryResponseType {
+    #[default]
+    Blob,
+    ArrayBuffer,
+}
+
+// Settings given on the command line, applied over every target
+#[derive(Debug, Default)]
+pub struct SettingsOverrides {
+    pub output_dir: Option<String>,
+    pub swagger_endpoint: Option<String>,
+    pub mortar_endpoint: Option<String>,
+    pub swagger_file: Option<PathBuf>,
+}
+
+impl SettingsOverrides {
+    fn apply(
+        &self,
+        mut builder: ConfigBuilder<DefaultState>,
+    ) -> anyhow::Result<ConfigBuilder<DefaultState>> {
+        let origin = COMMAND_LINE_ORIGIN.to_owned();
+        let overrides = [
+            ("output_dir", self.output_dir.clone()),
+            ("swagger_endpoint", self.swagger_endpoint.clone()),
+            ("mortar_endpoint", self.mortar_endpoint.clone()),
+            (
+                "swagger_file",
+                self.swagger_file.as_ref().map(|f| f.display().to_string()),
+            ),
+        ];
+
+        for (key, value) in overrides {
+            if let Some(value) = value {
+                builder = builder.set_override(key, Value::new(Some(&origin), ValueKind::String(value)))?;
+            }
+        }
+
+        // A swagger document given on the command line replaces the target's sources
+        if self.swagger_endpoint.is_some() || self.swagger_file.is_some() {
+            builder = builder.set_override("sources", Value::new(Some(&origin), ValueKind::Array(vec![])))?;
+        }
+
+        Ok(builder)
+    }
+}
+
+const COMMAND_LINE_ORIGIN: &str = "the command line";
+
 impl Settings {
-    pub fn new() -> Result<Self, ConfigError> {
-        let mut settings = config::Config::default();
-        settings
-            // Add in `./mortar.toml`
-            .merge(config::File::with_name("mortar"))?
+    // One set of settings per `[[targets]]` table, each merged over the root settings.
+    // Without any targets the root settings are the only target.
+    pub fn load_targets(
+        config_file: Option<&Path>,
+        overrides: &SettingsOverrides,
+    ) -> anyhow::Result<Vec<Self>> {
+        let mut results = vec![];
+
+        for (index, (_, config)) in Self::load_target_configs(config_file, overrides)?
+            .into_iter()
+            .enumerate()
+        {
+            let target: Settings = config
+                .try_deserialize()
+                .with_context(|| format!("Invalid settings for target {}", index))?;
+
+            results.push(target);
+        }
+
+        let mut output_dirs = HashSet::new();
+        for target in &results {
+            if !output_dirs.insert(&target.output_dir) {
+                Err(anyhow!(
+                    "Multiple targets write to {}, each target needs its own output_dir",
+                    &target.output_dir
+                ))?;
+            }
+        }
+
+        Ok(results)
+    }
+
+    // The root `[http]` settings, so `mortar init` can reach a backend behind auth before the rest of mortar.toml exists
+    pub fn load_http(config_file: Option<&Path>) -> anyhow::Result<HttpSettings> {
+        let file = match config_file {
+            Some(path) => config::File::from(path),
+            None => config::File::with_name("mortar"),
+        };
+
+        let settings = Config::builder()
+            .add_source(file.required(false))
+            .add_source(config::Environment::with_prefix("MORTAR").source(Some(settings_env())))
+            .build()?;
+
+        match settings.get::<HttpSettings>("http") {
+            Ok(http) => Ok(http),
+            Err(config::ConfigError::NotFound(_)) => Ok(HttpSettings::default()),
+            Err(e) => Err(e).context("Invalid http settings"),
+        }
+    }
+
+    // The merged configuration of each target, named by where it is declared
+    pub fn load_target_configs(
+        config_file: Option<&Path>,
+        overrides: &SettingsOverrides,
+    ) -> anyhow::Result<Vec<(String, Config)>> {
+        // Add in `./mortar.toml` unless another file is given
+        let file = match config_file {
+            Some(path) => config::File::from(path),
+            None => config::File::with_name("mortar"),
+        };
+
+        let file_settings = Config::builder().add_source(file.clone()).build()?;
+        check_keys(&file_settings.collect()?)?;
+
+        let settings = Config::builder()
+            .add_source(file)
             // Add in settings from the environment (with a prefix of MORTAR)
             // Eg.. `MORTAR_DEBUG=1 ./target/app` would set the `debug` key
-            .merge(config::Environment::with_prefix("MORTAR"))?;
+            .add_source(config::Environment::with_prefix("MORTAR").source(Some(settings_env())))
+            .build()?;
+
+        let targets = match settings.get_array("targets") {
+            Ok(targets) => targets,
+            Err(config::ConfigError::NotFound(_)) => {
+                let builder = overrides.apply(Config::builder().add_source(settings))?;
+                return Ok(vec![("root".to_owned(), builder.build()?)]);
+            }
+            Err(e) => Err(e).context("targets must be a list of tables")?,
+        };
+
+        let mut results = Vec::with_capacity(targets.len());
+
+        for (index, target) in targets.into_iter().enumerate() {
+            let table = target
+                .into_table()
+                .with_context(|| format!("Target {} is not a table", index))?;
+
+            let mut builder = Config::builder().add_source(settings.clone());
+            for (key, value) in table {
+                builder = builder.set_override(key, value)?;
+            }
 
-        // You can deserialize (and thus freeze) the entire configuration as
-        settings.try_deserialize()
+            results.push((format!("targets[{}]", index), overrides.apply(builder)?.build()?));
+        }
+
+        Ok(results)
+    }
+
+    // Every setting of a target alongside where its value came from, for `mortar config show`
+    pub fn describe(&self, config: &Config) -> anyhow::Result<Vec<(String, serde_json::Value, String)>> {
+        let explicit = config.collect()?;
+        let values = match serde_json::to_value(self)? {
+            serde_json::Value::Object(values) => values,
+            _ => anyhow::bail!("Settings should serialise to an object"),
+        };
+
+        let described = values
+            .into_iter()
+            .map(|(key, value)| {
+                let origin = match explicit.get(&key) {
+                    Some(explicit) => value_origin(explicit).unwrap_or(COMMAND_LINE_ORIGIN).to_owned(),
+                    None => "default".to_owned(),
+                };
+
+                (key, value, origin)
+            })
+            .collect();
+
+        Ok(described)
+    }
+
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.output_dir)
+    }
+
+    // The schema of mortar.toml for editors. Settings can be split between the root and its targets so none are
+    // required on their own.
+    pub fn json_schema() -> RootSchema {
+        let mut schema = schemars::schema_for!(Settings);
+        schema.schema.metadata().title = Some("mortar.toml".to_owned());
+        schema.schema.object().required.clear();
+
+        let mut target = schema.schema.clone();
+        target.metadata().title = Some("Target".to_owned());
+        schema.definitions.insert("Target".to_owned(), target.into());
+
+        let mut targets = SchemaObject {
+            instance_type: Some(InstanceType::Array.into()),
+            array: Some(Box::new(ArrayValidation {
+                items: Some(Schema::new_ref("#/definitions/Target".to_owned()).into()),
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+        targets.metadata().description =
+            Some("Outputs generated in one run, each merged over the settings at the root".to_owned());
+        schema
+            .schema
+            .object()
+            .properties
+            .insert("targets".to_owned(), targets.into());
+
+        schema
+    }
+
+    // The target's own swagger_endpoint/swagger_file make up its only source when it doesn't list any
+    pub fn swagger_sources(&self) -> Vec<SwaggerSource> {
+        if !self.sources.is_empty() {
+            return self.sources.clone();
+        }
+
+        vec![SwaggerSource {
+            swagger_endpoint: self.swagger_endpoint.clone(),
+            swagger_file: self.swagger_file.clone(),
+            prefix: None,
+        }]
+    }
+
+    // Every source is read from disk so there is no backend to watch
+    pub fn is_file_only(&self) -> bool {
+        self.swagger_sources().iter().all(|s| s.swagger_file.is_some())
+    }
+
+    pub fn includes_action_group(&self, action_group: &str) -> bool {
+        (self.action_groups.is_empty() || self.action_groups.iter().any(|g| g == action_group))
+            && !self.excluded_action_groups.iter().any(|g| g == action_group)
+    }
+}
+
+// Tables and lists don't keep an origin of their own, so use the first of their values'
+fn value_origin(value: &Value) -> Option<&str> {
+    if let Some(origin) = value.origin() {
+        return Some(origin);
+    }
+
+    match &value.kind {
+        ValueKind::Table(table) => table.values().find_map(value_origin),
+        ValueKind::Array(values) => values.iter().find_map(value_origin),
+        _ => None,
     }
 }
+
+// The environment without MORTAR_LOG, which sets the log level rather than a setting
+fn settings_env() -> config::Map<String, String> {
+    std::env::vars()
+        .filter(|(key, _)| key != "MORTAR_LOG")
+        .collect()
+}
+
+// Unknown keys are most likely typos, so fail rather than quietly ignoring them
+fn check_keys(file: &config::Map<String, Value>) -> anyhow::Result<()> {
+    let settings_keys = field_names::<Settings>();
+    let mut root_keys = settings_keys.to_vec();
+    root_keys.push("targets");
+
+    let mut errors = vec![];
+    check_table(file, "", &root_keys, &mut errors);
+
+    if let Some(targets) = file.get("targets").and_then(|t| t.clone().into_array().ok()) {
+        for (index, target) in targets.into_iter().enumerate() {
+            if let Ok(target) = target.into_table() {
+                let path = format!("targets[{}].", index);
+                check_table(&target, &path, settings_keys, &mut errors);
+            }
+        }
+    }
+
+    if !errors.is_empty() {
+        anyhow::bail!(errors.join("\n"));
+    }
+
+    Ok(())
+}
+
+// Checks a target's keys, along with the tables nested in it. Header names are free form so aren't checked.
+fn check_table(
+    table: &config::Map<String, Value>,
+    path: &str,
+    known: &[&str],
+    errors: &mut Vec<String>,
+) {
+    for (key, value) in table {
+        if !known.contains(&key.as_str()) {
+            let full = format!("{}{}", path, key);
+            errors.push(unknown_key_error(&full, key, value, known));
+            continue;
+        }
+
+        match key.as_str() {
+            "sources" => {
+                let sources = value.clone().into_array().unwrap_or_default();
+                for (index, source) in sources.into_iter().enumerate() {
+                    let path = format!("{}sources[{}].", path, index);
+                    check_nested(source, &path, field_names::<SwaggerSource>(), errors);
+                }
+            }
+            "http" => {
+                let path = format!("{}http.", path);
+                let http = value.clone().into_table().unwrap_or_default();
+                check_nested(value.clone(), &path, field_names::<HttpSettings>(), errors);
+
+                if let Some(basic_auth) = http.get("basic_auth") {
+                    let path = format!("{}basic_auth.", path);
+                    check_nested(basic_auth.clone(), &path, field_names::<BasicAuth>(), errors);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn check_nested(table: Value, path: &str, known: &[&str], errors: &mut Vec<String>) {
+    for (key, value) in table.into_table().unwrap_or_default() {
+        if !known.contains(&key.as_str()) {
+            let full = format!("{}{}", path, key);
+            errors.push(unknown_key_error(&full, &key, &value, known));
+        }
+    }
+}
+
+fn unknown_key_error(full: &str, key: &str, value: &Value, known: &[&str]) -> String {
+    let error = format!(
+        "Unknown setting {} in {}",
+        full,
+        value.origin().unwrap_or("the settings file")
+    );
+
+    match string_tools::closest_match(key, known) {
+        Some(suggestion) => format!("{}, did you mean {}?", error, suggestion),
+        None => error,
+    }
+}
+
+// The fields serde expects for a struct, read by asking it to deserialize from a deserializer that only records them
+fn field_names<'de, T: Deserialize<'de>>() -> &'static [&'static str] {
+    struct FieldNames<'a>(&'a mut &'static [&'static str]);
+
+    impl<'de> Deserializer<'de> for FieldNames<'_> {
+        type Error = de::value::Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+            Err(de::Error::custom("Not a struct"))
+        }
+
+        fn deserialize_struct<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            fields: &'static [&'static str],
+            _visitor: V,
+        ) -> Result<V::Value, Self::Error> {
+            *self.0 = fields;
+            Err(de::Error::custom("Fields recorded"))
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+            option unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier ignored_any
+        }
+    }
+
+    let mut fields: &'static [&'static str] = &[];
+    let _ = T::deserialize(FieldNames(&mut fields));
+    fields
+}
@@ -1,11 +1,73 @@
-pub fn ensure_camel_case(str: &mut String) {
+pub fn ensure_camel_case(str: &mut str) {
     if let Some(c) = str.get_mut(0..1) {
         c.make_ascii_lowercase();
     }
 }
 
-pub fn ensure_pascal_case(str: &mut String) {
+pub fn ensure_pascal_case(str: &mut str) {
     if let Some(c) = str.get_mut(0..1) {
         c.make_ascii_uppercase();
     }
 }
+
+// Quotes an object key when it isn't a valid identifier e.g. `content-type`
+pub fn quote_property_name(name: &str) -> String {
+    let is_identifier = !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+
+    if is_identifier {
+        name.to_owned()
+    } else {
+        serde_json::to_string(name).unwrap()
+    }
+}
+
+// Splits a PascalCase name into words e.g. `InProgress` to `In Progress`
+pub fn humanise(name: &str) -> String {
+    let mut result = String::with_capacity(name.len() + 4);
+    let mut previous: Option<char> = None;
+
+    for c in name.chars() {
+        if c == '_' {
+            result.push(' ');
+        } else {
+            if c.is_ascii_uppercase()
+                && previous.is_some_and(|p| p.is_ascii_lowercase() || p.is_ascii_digit())
+            {
+                result.push(' ');
+            }
+            result.push(c);
+        }
+        previous = Some(c);
+    }
+
+    result
+}
+
+// The candidate closest to a misspelt name, if any is close enough to be what was meant
+pub fn closest_match<'a>(name: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    candidates
+        .iter()
+        .map(|candidate| (edit_distance(name, candidate), *candidate))
+        .filter(|(distance, candidate)| *distance <= 2.max(candidate.len() / 3))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
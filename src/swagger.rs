@@ -1,27 +1,265 @@
+use crate::diagnostics::{self, Diagnostic, DiagnosticCode};
+use crate::settings::{self, HttpSettings};
+use crate::string_tools;
 use anyhow::Context;
-use serde::{Deserialize, Deserializer};
-use std::collections::{BTreeMap, HashMap};
+use serde::Deserialize;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::Duration;
 
 #[derive(Deserialize, Debug)]
+#[allow(dead_code)]
 pub struct Swagger {
     #[serde(rename = "openapi")]
     pub open_api: String,
-    pub info: HashMap<String, String>,
+    pub info: HashMap<String, serde_json::Value>,
+    // 3.1 documents may only have webhooks
+    #[serde(default)]
     pub paths: HashMap<String, SwaggerPath>,
+    #[serde(default)]
     pub components: SwaggerComponents,
 }
 
-#[derive(Deserialize, Debug)]
+impl Swagger {
+    // Parses a swagger document, applying a source's prefix before anything refers to its names
+    pub fn from_json(mut document: serde_json::Value, prefix: Option<&str>) -> anyhow::Result<Self> {
+        if let Some(prefix) = prefix {
+            apply_prefix(&mut document, prefix);
+        }
+
+        serde_json::from_value(document).context("Failed to deserialise swagger.json")
+    }
+}
+
+// Renames a document's components to `{Prefix}{Name}` and moves its namespaces and action groups under the prefix
+fn apply_prefix(document: &mut serde_json::Value, prefix: &str) {
+    let mut pascal = prefix.to_owned();
+    string_tools::ensure_pascal_case(&mut pascal);
+    let mut camel = prefix.to_owned();
+    string_tools::ensure_camel_case(&mut camel);
+
+    let mut renamed = HashSet::new();
+    if let Some(components) = document.get_mut("components").and_then(|v| v.as_object_mut()) {
+        for (kind, entries) in components.iter_mut() {
+            if let Some(entries) = entries.as_object_mut() {
+                for (name, value) in std::mem::take(entries) {
+                    renamed.insert(format!("#/components/{}/{}", kind, name));
+                    entries.insert(format!("{}{}", pascal, name), value);
+                }
+            }
+        }
+    }
+
+    rename_references(document, &renamed, &pascal);
+
+    if let Some(schemas) = document
+        .pointer_mut("/components/schemas")
+        .and_then(|v| v.as_object_mut())
+    {
+        for schema in schemas.values_mut() {
+            if let Some(namespace) = schema.pointer_mut("/x-mtr/ns").and_then(|v| v.as_array_mut()) {
+                namespace.insert(0, pascal.clone().into());
+            }
+        }
+    }
+
+    if let Some(paths) = document.get_mut("paths").and_then(|v| v.as_object_mut()) {
+        for endpoint in paths.values_mut().filter_map(|v| v.as_object_mut()).flat_map(|p| p.values_mut()) {
+            if let Some(group) = endpoint.pointer_mut("/x-mtr/ag") {
+                if let Some(name) = group.as_str() {
+                    let mut name = name.to_owned();
+                    string_tools::ensure_pascal_case(&mut name);
+                    *group = format!("{}{}", camel, name).into();
+                }
+            }
+        }
+    }
+}
+
+// Rewrites the `$ref`s and x-mtr generic type names pointing at a renamed component, including generic
+// arguments like `#/components/schemas/Foo[]` and `$defs` paths beneath a schema. Other strings such as
+// descriptions and examples are left as they are, as are built in types such as `#/components/schemas/String`.
+fn rename_references(value: &mut serde_json::Value, renamed: &HashSet<String>, prefix: &str) {
+    match value {
+        serde_json::Value::Array(items) => {
+            for item in items {
+                rename_references(item, renamed, prefix);
+            }
+        }
+        serde_json::Value::Object(fields) => {
+            for (key, field) in fields.iter_mut() {
+                match key.as_str() {
+                    "$ref" => rename_reference(field, renamed, prefix),
+                    "x-mtr" => {
+                        for names in ["ga", "gm"] {
+                            if let Some(names) = field.get_mut(names) {
+                                rename_type_names(names, renamed, prefix);
+                            }
+                        }
+                    }
+                    _ => rename_references(field, renamed, prefix),
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+// Generic arguments (`ga`) and properties (`gm`) name their types by reference, nested in arrays for generic types
+fn rename_type_names(value: &mut serde_json::Value, renamed: &HashSet<String>, prefix: &str) {
+    match value {
+        serde_json::Value::String(_) => rename_reference(value, renamed, prefix),
+        serde_json::Value::Array(items) => {
+            for item in items {
+                rename_type_names(item, renamed, prefix);
+            }
+        }
+        serde_json::Value::Object(fields) => {
+            for field in fields.values_mut() {
+                rename_type_names(field, renamed, prefix);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn rename_reference(value: &mut serde_json::Value, renamed: &HashSet<String>, prefix: &str) {
+    let (kind, name) = match value
+        .as_str()
+        .and_then(|r| r.strip_prefix("#/components/"))
+        .and_then(|r| r.split_once('/'))
+    {
+        Some(parts) => parts,
+        None => return,
+    };
+
+    // Nullable generic arguments keep their marker in front of the renamed component
+    let unwrapped = name.trim_start_matches("Nullable__");
+    let nullable = &name[..name.len() - unwrapped.len()];
+    let base = unwrapped.split(['/', '[']).next().unwrap_or_default();
+
+    if renamed.contains(&format!("#/components/{}/{}", kind, base)) {
+        *value = format!("#/components/{}/{}{}{}", kind, nullable, prefix, unwrapped).into();
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
 pub struct SwaggerComponents {
+    #[serde(default)]
     pub schemas: BTreeMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub parameters: BTreeMap<String, serde_json::Value>,
+    #[serde(default, rename = "requestBodies")]
+    pub request_bodies: BTreeMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub responses: BTreeMap<String, serde_json::Value>,
 }
 
-#[derive(Deserialize, Debug)]
-pub struct SwaggerSchema {
-    #[serde(rename = "type")]
-    pub schema_type: String,
-    #[serde(flatten)]
-    pub fields: BTreeMap<String, serde_json::Value>,
+impl SwaggerComponents {
+    // Adds another source's components. The same name may only be defined twice if both definitions match.
+    pub fn merge(&mut self, other: SwaggerComponents, source: &str) -> anyhow::Result<()> {
+        let SwaggerComponents {
+            schemas,
+            parameters,
+            request_bodies,
+            responses,
+        } = other;
+
+        merge_components(&mut self.schemas, schemas, "schemas", source)?;
+        merge_components(&mut self.parameters, parameters, "parameters", source)?;
+        merge_components(&mut self.request_bodies, request_bodies, "requestBodies", source)?;
+        merge_components(&mut self.responses, responses, "responses", source)?;
+
+        Ok(())
+    }
+
+    // 3.1 schemas can nest their own definitions in `$defs`, referenced as `#/components/schemas/Foo/$defs/Bar`.
+    // Lift them up to schemas under that path, in the namespace of the schema that defines them.
+    pub fn hoist_defs(&mut self) {
+        let mut pending = self
+            .schemas
+            .iter()
+            .map(|(name, schema)| (name.clone(), schema.clone()))
+            .collect::<Vec<_>>();
+
+        while let Some((parent_name, parent)) = pending.pop() {
+            let defs = match parent.get("$defs").and_then(|v| v.as_object()) {
+                Some(defs) => defs,
+                None => continue,
+            };
+
+            for (def_name, def) in defs {
+                let name = format!("{}/$defs/{}", parent_name, def_name);
+                let mut def = def.clone();
+
+                if def.get("x-mtr").is_none() {
+                    if let Some(namespace) = parent.get("x-mtr").and_then(|v| v.get("ns")) {
+                        def["x-mtr"] = serde_json::json!({ "ns": namespace, "ne": def_name });
+                    }
+                }
+
+                pending.push((name.clone(), def.clone()));
+                self.schemas.insert(name, def);
+            }
+        }
+    }
+
+    // Follows `$ref`s to shared parameters, request bodies and responses. Schema references are left to MortarType.
+    pub fn resolve<'a>(&'a self, value: &'a serde_json::Value) -> anyhow::Result<&'a serde_json::Value> {
+        let mut current = value;
+
+        // Refs can point at other refs, but only so far
+        for _ in 0..16 {
+            let reference = match current.get("$ref").and_then(|v| v.as_str()) {
+                Some(reference) => reference,
+                None => return Ok(current),
+            };
+
+            let (kind, name) = reference
+                .strip_prefix("#/components/")
+                .and_then(|r| r.split_once('/'))
+                .with_context(|| format!("Unsupported reference {}", reference))?;
+
+            let components = match kind {
+                "parameters" => &self.parameters,
+                "requestBodies" => &self.request_bodies,
+                "responses" => &self.responses,
+                _ => anyhow::bail!("Unexpected reference to {} in {}", kind, reference),
+            };
+
+            // Unescape the JSON pointer segment
+            let name = name.replace("~1", "/").replace("~0", "~");
+
+            current = components
+                .get(&name)
+                .with_context(|| format!("Failed to find component {}", reference))?;
+        }
+
+        anyhow::bail!("Reference {:?} is nested too deeply", value.get("$ref"))
+    }
+}
+
+fn merge_components(
+    existing: &mut BTreeMap<String, serde_json::Value>,
+    added: BTreeMap<String, serde_json::Value>,
+    kind: &str,
+    source: &str,
+) -> anyhow::Result<()> {
+    for (name, value) in added {
+        match existing.get(&name) {
+            Some(current) if current != &value => Err(Diagnostic::error(
+                DiagnosticCode::SourceConflict,
+                format!("Source {} defines {} differently to an earlier source", source, name),
+            )
+            .at(format!("#/components/{}/{}", kind, diagnostics::pointer_segment(&name)))
+            .suggest("Give the sources a prefix"))?,
+            _ => {
+                existing.insert(name, value);
+            }
+        }
+    }
+
+    Ok(())
 }
 
 #[derive(Deserialize, Debug)]
@@ -33,6 +271,7 @@ pub struct SwaggerPath {
 }
 
 #[derive(Deserialize, Debug, Clone)]
+#[allow(dead_code)]
 pub struct SwaggerEndpoint {
     pub tags: Vec<String>,
     pub description: Option<String>,
@@ -54,19 +293,121 @@ pub struct MortarEndpointMeta {
 
 pub struct SwaggerApi {
     client: reqwest::Client,
+    bearer_token: Option<String>,
+    basic_auth: Option<(String, Option<String>)>,
+    timeout: Duration,
 }
 
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
 impl SwaggerApi {
-    pub fn new() -> Self {
-        SwaggerApi {
-            client: reqwest::Client::new(),
+    pub fn new(http: &HttpSettings) -> anyhow::Result<Self> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &http.headers {
+            let value = settings::interpolate_env(value)
+                .with_context(|| format!("Failed to read http.headers.{}", name))?;
+            headers.insert(
+                HeaderName::from_bytes(name.as_bytes())
+                    .with_context(|| format!("Invalid header name {}", name))?,
+                HeaderValue::from_str(&value)
+                    .with_context(|| format!("Invalid value for header {}", name))?,
+            );
+        }
+
+        let mut builder = reqwest::Client::builder()
+            .default_headers(headers)
+            .danger_accept_invalid_certs(http.danger_accept_invalid_certs);
+
+        if let Some(path) = &http.ca_certificate {
+            let pem = std::fs::read(path)
+                .with_context(|| format!("Failed to read CA certificate {}", path.display()))?;
+            for certificate in reqwest::Certificate::from_pem_bundle(&pem)
+                .with_context(|| format!("Invalid CA certificate {}", path.display()))?
+            {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        if let Some(path) = &http.client_certificate {
+            let pem = std::fs::read(path)
+                .with_context(|| format!("Failed to read client certificate {}", path.display()))?;
+            let identity = reqwest::Identity::from_pem(&pem).with_context(|| {
+                format!(
+                    "Invalid client certificate {}, it should hold the certificate and its private key",
+                    path.display()
+                )
+            })?;
+            builder = builder.identity(identity);
         }
+
+        if let Some(proxy) = &http.proxy {
+            let proxy = settings::interpolate_env(proxy).context("Failed to read http.proxy")?;
+            builder = builder.proxy(reqwest::Proxy::all(&proxy).context("Invalid http.proxy")?);
+        }
+
+        let timeout = http.timeout_secs.map_or(DEFAULT_TIMEOUT, Duration::from_secs);
+        builder = builder.connect_timeout(timeout);
+
+        let bearer_token = http
+            .bearer_token
+            .as_deref()
+            .map(settings::interpolate_env)
+            .transpose()
+            .context("Failed to read http.bearer_token")?;
+
+        let basic_auth = match &http.basic_auth {
+            Some(auth) => {
+                let password = auth
+                    .password
+                    .as_deref()
+                    .map(settings::interpolate_env)
+                    .transpose()
+                    .context("Failed to read http.basic_auth.password")?;
+                let username = settings::interpolate_env(&auth.username)
+                    .context("Failed to read http.basic_auth.username")?;
+                Some((username, password))
+            }
+            None => None,
+        };
+
+        Ok(SwaggerApi {
+            client: builder.build().context("Failed to create http client")?,
+            bearer_token,
+            basic_auth,
+            timeout,
+        })
+    }
+
+    // A request with the configured auth
+    fn get(&self, endpoint: &str) -> reqwest::RequestBuilder {
+        let mut request = self.client.get(endpoint);
+
+        if let Some(token) = &self.bearer_token {
+            request = request.bearer_auth(token);
+        }
+
+        if let Some((username, password)) = &self.basic_auth {
+            request = request.basic_auth(username, password.as_ref());
+        }
+
+        request
     }
 
-    pub async fn get_swagger_info(&self, endpoint: &str) -> anyhow::Result<Swagger> {
+    pub async fn get_swagger_info(
+        &self,
+        endpoint: &str,
+        prefix: Option<&str>,
+    ) -> anyhow::Result<Swagger> {
+        let document = self.get_swagger_document(endpoint).await?;
+
+        Swagger::from_json(document, prefix)
+    }
+
+    // The swagger.json as it was served, before anything is read from it
+    pub async fn get_swagger_document(&self, endpoint: &str) -> anyhow::Result<serde_json::Value> {
         let response = self
-            .client
             .get(endpoint)
+            .timeout(self.timeout)
             .send()
             .await
             .context("Api call to swagger.json endpoint failed")?;
@@ -74,18 +415,16 @@ impl SwaggerApi {
         let status = response.status();
 
         if !status.is_success() {
-            eprintln!("Swagger endpoint status code: {:?}", &status);
+            log::error!("Swagger endpoint status code: {:?}", &status);
             anyhow::bail!(
                 "Call to swagger endpoint unsuccessful, please check saffron console for more information"
             );
         }
 
-        let result = response
-            .json::<Swagger>()
+        response
+            .json::<serde_json::Value>()
             .await
-            .context("Failed to deserialise swagger.json")?;
-
-        Ok(result)
+            .context("Failed to deserialise swagger.json")
     }
 
     pub async fn get_current_build_id(
@@ -93,15 +432,17 @@ impl SwaggerApi {
         endpoint: &str,
         id: Option<&str>,
     ) -> anyhow::Result<String> {
-        let mut request = self.client.get(endpoint);
+        let mut request = self.get(endpoint);
 
-        if let Some(id) = id {
-            request = request.query(&[("lastSeenId", id)]);
+        // Without an id to compare against the backend answers straight away, otherwise it blocks until it restarts
+        match id {
+            Some(id) => request = request.query(&[("lastSeenId", id)]),
+            None => request = request.timeout(self.timeout),
         }
         let response = request
             .send()
             .await
-            .context("Api call to swagger.json endpoint failed")?;
+            .context("Api call to build id endpoint failed")?;
 
         match response.error_for_status() {
             Ok(response) => Ok(response
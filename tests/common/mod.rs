@@ -0,0 +1,145 @@
+// Helpers shared by the integration tests, each test binary only uses some of them
+#![allow(dead_code)]
+
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::thread;
+use std::time::Duration;
+
+// A scratch directory to run mortar in, removed once the test is done
+pub struct Project {
+    pub dir: PathBuf,
+}
+
+impl Project {
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("mortar-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        Self { dir }
+    }
+
+    // A project with `settings` generating into ./output from its swagger.json
+    pub fn with_swagger(name: &str, settings: &str, swagger: &serde_json::Value) -> Self {
+        let project = Self::new(name);
+        project.write("mortar.toml", format!("output_dir = \"./output\"\n{}", settings));
+        project.write("swagger.json", swagger.to_string());
+
+        project
+    }
+
+    pub fn write(&self, file: &str, contents: impl AsRef<[u8]>) {
+        fs::write(self.dir.join(file), contents).unwrap();
+    }
+
+    pub fn exists(&self, file: &str) -> bool {
+        self.dir.join(file).exists()
+    }
+
+    pub fn mortar(&self) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_mortar"));
+        command.env_remove("MORTAR_LOG").current_dir(&self.dir);
+        command
+    }
+
+    // Runs mortar against the project's swagger.json
+    pub fn run(&self, args: &[&str]) -> Output {
+        self.mortar()
+            .args(["--swagger-file", "swagger.json"])
+            .args(args)
+            .output()
+            .unwrap()
+    }
+
+    // Runs mortar, failing the test if it doesn't succeed
+    pub fn generate(&self) -> &Self {
+        let output = self.run(&[]);
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        self
+    }
+
+    // A generated file, relative to the output directory
+    pub fn read(&self, file: &str) -> String {
+        fs::read_to_string(self.dir.join("output").join(file))
+            .unwrap_or_else(|e| panic!("Failed to read {}: {}", file, e))
+    }
+}
+
+impl Drop for Project {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+pub fn fixture_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+pub fn fixture(name: &str) -> serde_json::Value {
+    serde_json::from_str(&fs::read_to_string(fixture_path(name)).unwrap()).unwrap()
+}
+
+// Generates a fixture with the given settings, failing the test if mortar doesn't succeed
+pub fn generate(name: &str, settings: &str, fixture_name: &str) -> Project {
+    let project = Project::with_swagger(name, settings, &fixture(fixture_name));
+    project.generate();
+    project
+}
+
+// Asserts the generated source contains `expected`, ignoring differences in whitespace
+pub fn assert_contains(source: &str, expected: &str) {
+    let squash = |s: &str| s.split_whitespace().collect::<Vec<_>>().join(" ");
+    assert!(
+        squash(source).contains(&squash(expected)),
+        "Expected to find\n{}\nin\n{}",
+        expected,
+        source
+    );
+}
+
+// A stand-in backend on a free port, returning its address. `respond` gets the request line and headers of each
+// request and returns the body, or None to hold the connection open as a long polled build id would.
+pub fn serve(respond: impl Fn(&str, &[String]) -> Option<Vec<u8>> + Send + 'static) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = format!("http://{}", listener.local_addr().unwrap());
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut lines = BufReader::new(&stream)
+                .lines()
+                .map(|line| line.unwrap())
+                .take_while(|line| !line.is_empty())
+                .collect::<Vec<_>>();
+            let request = lines.remove(0);
+
+            let Some(body) = respond(&request, &lines) else {
+                thread::spawn(move || {
+                    thread::sleep(Duration::from_secs(60));
+                    drop(stream);
+                });
+                continue;
+            };
+
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            )
+            .unwrap();
+            stream.write_all(&body).unwrap();
+        }
+    });
+
+    address
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use common::{assert_contains, generate};
+
+#[test]
+fn sends_form_bodies() {
+    let project = generate("form-bodies", "", "content_types.json");
+    let files = project.read("endpoints/files.ts");
+
+    // Multipart leaves the content type to the browser for the boundary
+    assert_contains(
+        &files,
+        "(formTransform || makeFormData)(formParams, { file: \"Append\", title: \"Append\", }), { contentType: null, ...options },",
+    );
+    assert_contains(
+        &files,
+        "(formTransform || makeUrlEncodedData)(formParams, { term: \"Append\", }),",
+    );
+    assert_contains(
+        &files,
+        "contentType: \"application/x-www-form-urlencoded\",",
+    );
+}
+
+#[test]
+fn reads_text_and_binary_responses() {
+    let project = generate("response-content", "", "content_types.json");
+    let files = project.read("endpoints/files.ts");
+
+    // JSON wins when a response offers several content types
+    assert_contains(&files, "apiPost<number, \"files/uploadFile\">(");
+    assert_contains(
+        &files,
+        "apiGet<string, \"files/getReadme\">( \"files/getReadme\", `api/files/readme`, undefined, { responseType: \"text\", ...options, headers: { Accept: \"text/plain\", ...options?.headers }, },",
+    );
+    assert_contains(
+        &files,
+        "apiGet<Blob, \"files/downloadFile\">( \"files/downloadFile\", `api/files/${routeParams.id}`, undefined, { responseType: \"blob\", ...options, headers: { Accept: \"application/pdf\", ...options?.headers }, },",
+    );
+    assert_contains(&files, "apiPost<Blob, \"files/searchFiles\">(");
+}
+
+#[test]
+fn reads_binary_responses_as_array_buffers() {
+    let project = generate(
+        "array-buffer",
+        "binary_response_type = \"arraybuffer\"\n",
+        "content_types.json",
+    );
+    let files = project.read("endpoints/files.ts");
+
+    assert_contains(&files, "apiGet<ArrayBuffer, \"files/downloadFile\">(");
+    assert_contains(&files, "responseType: \"arraybuffer\",");
+}
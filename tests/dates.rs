@@ -0,0 +1,73 @@
+mod common;
+
+use common::{assert_contains, generate};
+
+#[test]
+fn revives_response_dates() {
+    let project = generate("dates", "revive_dates = true\n", "dates.json");
+
+    let events = project.read("Events.ts");
+    assert_contains(
+        &events,
+        "\"last-seen\": MortarDate; name: string; reminders: MortarDate[]; /** * @default \"2024-01-01T09:00:00Z\" */ startsAt: MortarDate;",
+    );
+    assert_contains(
+        &events,
+        "export const EventDtoDates: DateFieldMap = { \"last-seen\": true, reminders: true, startsAt: true, };",
+    );
+    // Declared defaults are parsed like a response would be
+    assert_contains(&events, "startsAt: parseMortarDate(\"2024-01-01T09:00:00Z\"),");
+
+    let endpoints = project.read("endpoints/events.ts");
+    // Responses are typed with their dates as strings until they're revived
+    assert_contains(
+        &endpoints,
+        "apiGet<Unrevived<EventDto>, \"events/getEvent\">(",
+    );
+    assert_contains(
+        &endpoints,
+        "\"events/getEvent\", (response: Unrevived<EventDto>) => reviveMortarDates<EventDto>(response, () => EventDtoDates), );",
+    );
+    // Responses without dates use makeAction's default
+    assert_contains(&endpoints, "options, ), \"events/countEvents\");");
+
+    let dates = project.read("dates.ts");
+    assert_contains(
+        &dates,
+        "export const parseMortarDate = (value: string): MortarDate => new Date(value);",
+    );
+    assert_contains(
+        &dates,
+        "export type Unrevived<T> = T extends MortarDate ? string",
+    );
+}
+
+#[test]
+fn uses_the_configured_date_library() {
+    let project = generate(
+        "date-library",
+        "revive_dates = true\ndate_library = \"../dates/mortarDates\"\n",
+        "dates.json",
+    );
+
+    let dates = project.read("dates.ts");
+    assert_contains(
+        &dates,
+        "export type { MortarDate } from \"../dates/mortarDates\"; export { parseMortarDate };",
+    );
+    // Unrevived refers to the library's MortarDate
+    assert_contains(&dates, "import type { MortarDate } from \"../dates/mortarDates\";");
+}
+
+#[test]
+fn leaves_dates_as_strings_by_default() {
+    let project = generate("dates-off", "", "dates.json");
+
+    let events = project.read("Events.ts");
+    assert_contains(
+        &events,
+        "reminders: string[]; /** * @default \"2024-01-01T09:00:00Z\" */ startsAt: string;",
+    );
+    assert!(!events.contains("EventDtoDates"));
+    assert!(!project.read("endpoints/events.ts").contains("reviveMortarDates"));
+}
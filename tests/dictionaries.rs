@@ -0,0 +1,17 @@
+mod common;
+
+use common::{assert_contains, generate};
+
+#[test]
+fn types_dictionaries_as_records() {
+    let project = generate("dictionaries", "", "dictionaries.json");
+    let shop = project.read("Shop.ts");
+
+    // A Dictionary is typed by its additionalProperties when present, otherwise left as any
+    assert_contains(&shop, "counts: Record<string, number>;");
+    assert_contains(&shop, "metadata: Record<string, any>;");
+    // Not every enum variant needs to be a key
+    assert_contains(&shop, "byStatus: Partial<Record<OrderStatus, string>>;");
+    assert_contains(&shop, "extras: Record<string, any>;");
+    assert_contains(&shop, "counts: {},");
+}
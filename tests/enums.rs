@@ -0,0 +1,74 @@
+mod common;
+
+use common::{assert_contains, generate};
+
+#[test]
+fn emits_named_enum_keys_labels_and_flags() {
+    let project = generate("enums", "", "enums.json");
+    let shop = project.read("Shop.ts");
+
+    assert_contains(
+        &shop,
+        "export const OrderStatus = { /** Awaiting payment */ \"Open\": 0, \"InProgress\": 1, \"Closed\": 2, } as const;",
+    );
+    // Labels are humanised from the name unless the backend supplies one
+    assert_contains(
+        &shop,
+        "export const OrderStatusLabels: Record<OrderStatus, string> = { 0: \"Open\", 1: \"In Progress\", 2: \"Closed for good\", };",
+    );
+    assert_contains(
+        &shop,
+        "export const Priority = { \"Low\": \"low\", \"High\": \"high\", \"Urgent\": \"urgent\\u001b\", } as const;",
+    );
+    // Values and labels are escaped as JSON strings
+    assert_contains(&shop, "\"urgent\\u001b\": \"Urgent\\u0007\",");
+
+    // Any combination of flags is a valid value
+    assert_contains(&shop, "export type Permissions = number;");
+    assert_contains(
+        &shop,
+        "export const PermissionsFlags = makeFlags(Permissions);",
+    );
+    assert_contains(&shop, "import { makeFlags } from \"mortar/lib\";");
+}
+
+#[test]
+fn keys_negative_values_in_every_style() {
+    for (style, labels) in [
+        ("const_object", "{ [-1]: \"Down\", 0: \"Level\", 1: \"Up\", }"),
+        ("union", "{ [-1]: \"Down\", 0: \"Level\", 1: \"Up\", }"),
+        (
+            "enum",
+            "{ [Direction[\"Down\"]]: \"Down\", [Direction[\"Level\"]]: \"Level\", [Direction[\"Up\"]]: \"Up\", }",
+        ),
+        (
+            "const_enum",
+            "{ [Direction[\"Down\"]]: \"Down\", [Direction[\"Level\"]]: \"Level\", [Direction[\"Up\"]]: \"Up\", }",
+        ),
+    ] {
+        let project = generate(
+            &format!("enum-style-{}", style),
+            &format!("enum_style = \"{}\"\n", style),
+            "enums.json",
+        );
+
+        assert_contains(
+            &project.read("Shop.ts"),
+            &format!(
+                "export const DirectionLabels: Record<Direction, string> = {};",
+                labels
+            ),
+        );
+    }
+}
+
+#[test]
+fn builds_flags_from_the_variants_of_numeric_enums() {
+    let project = generate("enum-flags", "enum_style = \"enum\"\n", "enums.json");
+
+    // Passing the enum itself would include its reverse mappings
+    assert_contains(
+        &project.read("Shop.ts"),
+        "export const PermissionsFlags = makeFlags({ \"None\": 0, \"Read\": 1, \"Write\": 2, \"Delete\": 4, });",
+    );
+}
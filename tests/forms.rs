@@ -0,0 +1,37 @@
+mod common;
+
+use common::{assert_contains, generate};
+
+#[test]
+fn exports_property_constraints() {
+    let project = generate("constraints", "", "users.json");
+
+    assert_contains(
+        &project.read("Accounts.ts"),
+        r#"export const UserDtoConstraints = {
+            age: { minimum: 18, maximum: 130 },
+            email: { required: true, maxLength: 100, email: true },
+            nickname: { pattern: "^[^\u0000-\u001f]*$" },
+            password: { required: true, minLength: 8 },
+            roles: { minItems: 1, maxItems: 3 },
+            username: { pattern: "^[a-z]+\\d*$" },
+        } as const;"#,
+    );
+}
+
+#[test]
+fn sends_input_types_without_read_only_properties() {
+    let project = generate("input-types", "", "users.json");
+
+    let accounts = project.read("Accounts.ts");
+    // Write only properties are never returned
+    assert_contains(&accounts, "password?: string;");
+    assert_contains(
+        &accounts,
+        "export type UserDtoInput = & Omit<UserDto, \"id\"> & Required<Pick<UserDto, \"password\">>;",
+    );
+
+    let users = project.read("endpoints/users.ts");
+    assert_contains(&users, "request: UserDtoInput;");
+    assert_contains(&users, "apiPost<UserDto, \"users/createUser\">(");
+}
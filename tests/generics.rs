@@ -0,0 +1,18 @@
+mod common;
+
+use common::{assert_contains, generate};
+
+#[test]
+fn resolves_generic_arguments_by_exact_type_name() {
+    let project = generate("generics", "", "generics.json");
+
+    // InternalNote isn't a number for starting with Int, Nullable__Int32 is
+    assert_contains(
+        &project.read("endpoints/notes.ts"),
+        "apiGet<Pair<InternalNote, number>, \"notes/getNote\">(",
+    );
+    assert_contains(
+        &project.read("Notes.ts"),
+        "export interface Pair<T0, T1> { first: T0; second: T1; }",
+    );
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use common::{fixture_path, serve, Project};
+use std::fs;
+use std::sync::mpsc;
+use std::time::Duration;
+
+#[test]
+fn sends_configured_auth_and_headers() {
+    // Serves the recursive fixture and a build id, sending back the headers of each request
+    let swagger = fs::read(fixture_path("recursive_schemas.json")).unwrap();
+    let (sender, requests) = mpsc::channel();
+    let address = serve(move |request, headers| {
+        let _ = sender.send(headers.iter().map(|line| line.to_lowercase()).collect::<Vec<_>>());
+
+        match request.starts_with("GET /mortar/buildId") {
+            true => Some(b"build-1".to_vec()),
+            false => Some(swagger.clone()),
+        }
+    });
+
+    let project = Project::new("http");
+    project.write(
+        "mortar.toml",
+        format!(
+            "output_dir = \"./output\"\n\
+             swagger_endpoint = \"{0}/api/swagger.json\"\n\
+             mortar_endpoint = \"{0}/mortar/buildId\"\n\
+             [http]\n\
+             bearer_token = \"${{MORTAR_TEST_TOKEN}}\"\n\
+             timeout_secs = 5\n\
+             [http.headers]\n\
+             X-Api-Version = \"2\"\n",
+            address
+        ),
+    );
+
+    let output = project
+        .mortar()
+        .env("MORTAR_TEST_TOKEN", "s3cret")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // The build id then the swagger.json
+    for _ in 0..2 {
+        let headers = requests.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(
+            headers.contains(&"authorization: bearer s3cret".to_owned()),
+            "{:?}",
+            headers
+        );
+        assert!(
+            headers.contains(&"x-api-version: 2".to_owned()),
+            "{:?}",
+            headers
+        );
+    }
+    assert!(project.exists("output/Shop/Catalog.ts"));
+
+    let output = project
+        .mortar()
+        .env_remove("MORTAR_TEST_TOKEN")
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("Environment variable MORTAR_TEST_TOKEN isn't set"),
+        "{}",
+        stderr
+    );
+}
@@ -0,0 +1,149 @@
+mod common;
+
+use common::{fixture_path, serve, Project};
+use std::fs;
+use std::process::Stdio;
+
+#[test]
+fn writes_settings_and_package_scripts() {
+    let project = Project::new("init");
+    project.write(
+        "package.json",
+        "{\n  \"name\": \"portal\",\n  \"scripts\": {\n    \"build\": \"vite build\"\n  }\n}\n",
+    );
+
+    // Nothing listens on the discard port so every probe fails
+    let output = project
+        .mortar()
+        .args([
+            "init",
+            "--backend-url",
+            "http://127.0.0.1:9",
+            "--output-dir",
+            "./src/mortar",
+        ])
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let settings = fs::read_to_string(project.dir.join("mortar.toml")).unwrap();
+    assert!(settings.contains("swagger_endpoint = \"http://127.0.0.1:9/api/swagger.json\""));
+    assert!(settings.contains("# mortar_endpoint = \"http://127.0.0.1:9/mortar/buildId\""));
+    assert!(settings.contains("output_dir = \"./src/mortar\""));
+
+    let package = fs::read_to_string(project.dir.join("package.json")).unwrap();
+    let package: serde_json::Value = serde_json::from_str(&package).unwrap();
+    assert_eq!(package["scripts"]["mortar"], "mortar");
+    assert_eq!(package["scripts"]["build"], "vite build");
+
+    // The written settings load
+    let show = project
+        .mortar()
+        .args(["config", "show"])
+        .output()
+        .unwrap();
+    assert!(
+        show.status.success(),
+        "{}",
+        String::from_utf8_lossy(&show.stderr)
+    );
+
+    // An existing mortar.toml is kept
+    let again = project
+        .mortar()
+        .args([
+            "init",
+            "--backend-url",
+            "http://127.0.0.1:9",
+            "--output-dir",
+            "./other",
+        ])
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    assert!(!again.status.success());
+}
+
+#[test]
+fn escapes_settings_and_only_edits_top_level_scripts() {
+    let project = Project::new("init-escape");
+    project.write(
+        "package.json",
+        "{\n  \"name\": \"portal\",\n  \"description\": \"has \\\"scripts\\\": {\",\n  \"config\": {\n    \"scripts\": {}\n  },\n  \"version\": \"1.0.0\",\n  \"dependencies\": {\n    \"zod\": \"3.23.8\",\n    \"axios\": \"1.7.7\"\n  }\n}\n",
+    );
+
+    let output = project
+        .mortar()
+        .args([
+            "init",
+            "--backend-url",
+            "http://127.0.0.1:9",
+            "--output-dir",
+            "./src/\"mortar\"\\api",
+        ])
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let settings = fs::read_to_string(project.dir.join("mortar.toml")).unwrap();
+    let settings: toml::Value = toml::from_str(&settings).unwrap();
+    assert_eq!(settings["output_dir"].as_str(), Some("./src/\"mortar\"\\api"));
+
+    let text = fs::read_to_string(project.dir.join("package.json")).unwrap();
+    let package: serde_json::Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(package["scripts"]["mortar"], "mortar");
+    assert_eq!(package["scripts"]["mortar:watch"], "mortar --watch");
+    assert_eq!(package["config"]["scripts"], serde_json::json!({}));
+    assert_eq!(package["description"], "has \"scripts\": {");
+
+    // The existing keys keep their order, nested ones included
+    let positions = ["\"name\"", "\"description\"", "\"config\"", "\"version\"", "\"zod\"", "\"axios\"", "\"mortar\""]
+        .map(|key| text.find(key).unwrap());
+    assert!(positions.windows(2).all(|pair| pair[0] < pair[1]), "{}", text);
+}
+
+#[test]
+fn probes_with_the_http_settings() {
+    // Only answers requests carrying the token
+    let swagger = fs::read(fixture_path("recursive_schemas.json")).unwrap();
+    let address = serve(move |request, headers| {
+        if !headers.iter().any(|h| h.eq_ignore_ascii_case("authorization: bearer s3cret")) {
+            return Some(b"unauthorised".to_vec());
+        }
+
+        match request.starts_with("GET /mortar/buildId") {
+            true => Some(b"build-1".to_vec()),
+            false => Some(swagger.clone()),
+        }
+    });
+
+    let project = Project::new("init-http");
+    project.write("mortar.toml", "[http]\nbearer_token = \"s3cret\"\n");
+
+    let output = project
+        .mortar()
+        .args(["init", "--force", "--backend-url", &address, "--output-dir", "./src/mortar"])
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(stderr.contains("Found swagger.json at"), "{}", stderr);
+
+    let settings = fs::read_to_string(project.dir.join("mortar.toml")).unwrap();
+    assert!(
+        settings.contains(&format!("\nmortar_endpoint = \"{}/mortar/buildId\"", address)),
+        "{}",
+        settings
+    );
+}
@@ -0,0 +1,23 @@
+mod common;
+
+use common::{assert_contains, generate};
+
+#[test]
+fn types_inline_objects() {
+    let project = generate("inline-objects", "", "inline_objects.json");
+
+    // Only the required properties are mandatory
+    let stock = project.read("endpoints/stock.ts");
+    assert_contains(
+        &stock,
+        "apiGet<{ total: number; warehouse?: string }, \"stock/getStock\">(",
+    );
+
+    // Keys that aren't identifiers are quoted as JS strings
+    let shop = project.read("Shop.ts");
+    assert_contains(
+        &shop,
+        "address: { \"post-code\": string; \"say \\\"hi\\\"\"?: string; street: string; \"zero\u{200b}width\"?: boolean; };",
+    );
+    assert_contains(&shop, "\"post-code\": \"\",");
+}
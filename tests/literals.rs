@@ -0,0 +1,27 @@
+mod common;
+
+use common::{assert_contains, generate};
+
+#[test]
+fn types_literals_and_tuples() {
+    let project = generate("literals", "", "shapes.json");
+    let drawing = project.read("Drawing.ts");
+
+    // Discriminators narrow to their literal
+    assert_contains(
+        &drawing,
+        "export interface CircleDto { centre: [number, number]; kind: \"circle\"; radius: number; }",
+    );
+    assert_contains(&drawing, "kind: \"path\";");
+    assert_contains(&drawing, "style: \"solid\" | \"dashed\";");
+    assert_contains(&drawing, "version: 2;");
+    // Open tuples end with a rest element of their items
+    assert_contains(&drawing, "points: [string, ...number[]];");
+
+    // Defaults pick the literal, or the first of a union
+    assert_contains(
+        &drawing,
+        "export const defaultCircleDto = (): CircleDto => ({ centre: [0, 0], kind: \"circle\", radius: 0, });",
+    );
+    assert_contains(&drawing, "style: \"solid\",");
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use common::{assert_contains, fixture, Project};
+
+#[test]
+fn merges_prefixed_sources_into_one_tree() {
+    let project = Project::new("sources");
+    project.write(
+        "mortar.toml",
+        r#"
+output_dir = "./output"
+
+[[sources]]
+swagger_file = "swagger.json"
+prefix = "billing"
+
+[[sources]]
+swagger_file = "swagger.json"
+prefix = "orders"
+"#,
+    );
+    project.write("swagger.json", fixture("recursive_schemas.json").to_string());
+
+    let output = project.mortar().output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    assert!(project.exists("output/lib.ts"));
+
+    // Each source's namespaces and action groups move under its prefix
+    let billing = project.read("endpoints/billingCatalog.ts");
+    assert!(billing.contains("from \"mortar/Billing/Shop/Catalog\""));
+    assert!(billing.contains("\"billingCatalog/"));
+    assert!(project.exists("output/endpoints/ordersCatalog.ts"));
+
+    let orders = project.read("Orders/Shop/Orders.ts");
+    assert!(orders.contains("from \"mortar/Orders/Shop/Customers\";"));
+}
+
+#[test]
+fn renames_only_references_to_prefixed_components() {
+    let project = Project::new("sources-references");
+    project.write(
+        "mortar.toml",
+        "output_dir = \"./output\"\n\n[[sources]]\nswagger_file = \"notes.json\"\nprefix = \"billing\"\n",
+    );
+    project.write("notes.json", fixture("generics.json").to_string());
+    let output = project.mortar().output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // Generic arguments follow the rename, nullable ones included
+    assert_contains(
+        &project.read("endpoints/billingNotes.ts"),
+        "apiGet<Pair<InternalNote, number>, \"billingNotes/getNote\">(",
+    );
+    // Examples and descriptions that look like references are left alone
+    assert_contains(
+        &project.read("Billing/Notes.ts"),
+        "@example \"#/components/schemas/InternalNote\"",
+    );
+}
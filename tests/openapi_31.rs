@@ -0,0 +1,52 @@
+mod common;
+
+use common::{assert_contains, fixture, generate, Project};
+
+#[test]
+fn parses_type_arrays_defs_and_prefix_items() {
+    let project = generate("openapi-31", "", "openapi_31.json");
+    let pets = project.read("Pets.ts");
+
+    assert_contains(&pets, "age: number | string;");
+    assert_contains(&pets, "kind: \"dog\";");
+    assert_contains(&pets, "@example \"Rex\"");
+    assert_contains(&pets, "nickname: string;");
+    // $defs are generated in the namespace of the schema defining them
+    assert_contains(&pets, "export interface Owner { name: string; }");
+    assert_contains(&pets, "owner: Owner;");
+    // Tuples are open unless items is false
+    assert_contains(&pets, "position: [number, number, ...unknown[]];");
+    assert_contains(&pets, "nothing: [];");
+}
+
+#[test]
+fn types_null_unions_as_nullable() {
+    let mut swagger = fixture("openapi_31.json");
+    swagger["components"]["schemas"]["PetDto"]["properties"]["legacy"] =
+        serde_json::json!({ "type": "string", "nullable": true });
+    let project = Project::with_swagger("openapi-31-null", "strict_or_null = true\n", &swagger);
+    let pets = project.generate().read("Pets.ts");
+
+    assert_contains(&pets, "nickname: string | null;");
+    assert_contains(&pets, "owner: Owner | null;");
+    assert_contains(&pets, "name: string;");
+    // 3.0's nullable is typed as it was before 3.1 support
+    assert_contains(&pets, "legacy: string;");
+}
+
+#[test]
+fn reports_arrays_without_items() {
+    let mut swagger = fixture("openapi_31.json");
+    swagger["components"]["schemas"]["PetDto"]["properties"]["tags"] =
+        serde_json::json!({ "type": "array" });
+    let project = Project::with_swagger("openapi-31-items", "", &swagger);
+
+    let output = project.run(&[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("Failed to parse property tags: Array doesn't specify items"),
+        "{}",
+        stderr
+    );
+}
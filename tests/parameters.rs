@@ -0,0 +1,62 @@
+mod common;
+
+use common::{assert_contains, generate};
+
+#[test]
+fn generates_header_params() {
+    let project = generate(
+        "header-params",
+        "ignored_headers = [\"authorization\"]\n",
+        "parameters.json",
+    );
+    let reports = project.read("endpoints/reports.ts");
+
+    assert_contains(
+        &reports,
+        "export interface GetReportHeaderParams { \"X-Tenant-Id\": string; \"If-None-Match\"?: string; }",
+    );
+    assert_contains(&reports, "headerParams: GetReportHeaderParams;");
+    assert_contains(
+        &reports,
+        "{ ...options, headers: { ...headerParams, ...options?.headers } },",
+    );
+
+    // Ignored headers and cookies are left out entirely
+    assert!(!reports.contains("Authorization"), "{}", reports);
+    assert!(!reports.contains("session"), "{}", reports);
+    assert!(!reports.contains("DeleteReportsHeaderParams"), "{}", reports);
+}
+
+#[test]
+fn only_quotes_header_names_that_need_it() {
+    let project = generate("header-names", "", "parameters.json");
+    let reports = project.read("endpoints/reports.ts");
+
+    assert_contains(
+        &reports,
+        "export interface DeleteReportsHeaderParams { Authorization?: string; }",
+    );
+    assert_contains(&reports, "\"X-Tenant-Id\": string;");
+}
+
+#[test]
+fn resolves_shared_components() {
+    let project = generate("shared-components", "", "shared_components.json");
+    let products = project.read("endpoints/products.ts");
+
+    assert_contains(
+        &products,
+        "export interface UpdateProductRouteParams { id: number; }",
+    );
+    // Described by a media type rather than a schema
+    assert_contains(
+        &products,
+        "export interface UpdateProductQueryParams { version: string; }",
+    );
+    assert_contains(
+        &products,
+        "export interface UpdateProductErrors { 404: ProblemDetails; }",
+    );
+    assert_contains(&products, "request: ProductDto;");
+    assert_contains(&products, "apiPut<ProductDto, \"products/updateProduct\">(");
+}
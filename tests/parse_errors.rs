@@ -0,0 +1,151 @@
+mod common;
+
+use common::{fixture, Project};
+
+// The recursive fixture with two broken schemas and an endpoint missing its mortar extensions
+fn setup(name: &str) -> Project {
+    let mut swagger = fixture("recursive_schemas.json");
+    let schemas = &mut swagger["components"]["schemas"];
+    schemas["OrderDto"]["x-mtr"]
+        .as_object_mut()
+        .unwrap()
+        .remove("ns");
+    schemas["CustomerDto"]["x-mtr"]
+        .as_object_mut()
+        .unwrap()
+        .remove("ne");
+    swagger["paths"]["/api/categories/tree"]["get"]
+        .as_object_mut()
+        .unwrap()
+        .remove("x-mtr");
+
+    Project::with_swagger(name, "", &swagger)
+}
+
+#[test]
+fn reports_every_parse_error() {
+    let project = setup("parse-errors");
+
+    let output = project.run(&[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    for expected in [
+        "Endpoint doesn't have mortar extensions at #/paths/~1api~1categories~1tree/get",
+        "Failed to parse schema CustomerDto: Type doesn't include name at #/components/schemas/CustomerDto",
+        "Failed to parse schema OrderDto: Type didn't include namespace at #/components/schemas/OrderDto",
+    ] {
+        assert!(stderr.contains(expected), "{}", stderr);
+    }
+
+    // Nothing is generated without --keep-going
+    assert!(!project.exists("output"));
+}
+
+#[test]
+fn keep_going_generates_what_parsed() {
+    let project = setup("keep-going");
+
+    let output = project.run(&["--keep-going", "--message-format", "json"]);
+    assert!(!output.status.success());
+
+    let errors = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .filter(|d| d["severity"] == "error")
+        .map(|d| {
+            (
+                d["code"].as_str().unwrap().to_owned(),
+                d["pointer"].as_str().unwrap().to_owned(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        errors,
+        [
+            (
+                "invalid-endpoint".to_owned(),
+                "#/paths/~1api~1categories~1tree/get".to_owned()
+            ),
+            (
+                "invalid-schema".to_owned(),
+                "#/components/schemas/CustomerDto".to_owned()
+            ),
+            (
+                "invalid-schema".to_owned(),
+                "#/components/schemas/OrderDto".to_owned()
+            ),
+        ]
+    );
+
+    assert!(project.exists("output/endpoints/customers.ts"));
+    assert!(project.exists("output/Shop/Catalog.ts"));
+    assert!(!project.exists("output/endpoints/catalog.ts"));
+}
+
+#[test]
+fn keep_going_skips_endpoints_referring_to_skipped_schemas() {
+    let mut swagger = fixture("recursive_schemas.json");
+    // Without x-mtr the enum has no namespace and is skipped
+    swagger["components"]["schemas"]["Kind"] =
+        serde_json::json!({ "type": "string", "enum": ["Small", "Large"] });
+    swagger["paths"]["/api/customers/{id}/kind"] = serde_json::json!({
+        "post": {
+            "tags": ["Customers"],
+            "x-mtr": { "an": "setCustomerKind", "ag": "customers" },
+            "requestBody": {
+                "content": {
+                    "multipart/form-data": {
+                        "schema": {
+                            "type": "object",
+                            "properties": { "kind": { "$ref": "#/components/schemas/Kind" } }
+                        }
+                    }
+                }
+            },
+            "responses": { "204": { "description": "No Content" } }
+        }
+    });
+    let project = Project::with_swagger("keep-going-references", "", &swagger);
+
+    let output = project.run(&["--keep-going", "--message-format", "json"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+
+    let errors = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .filter(|d| d["severity"] == "error")
+        .map(|d| d["pointer"].as_str().unwrap().to_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        errors,
+        [
+            "#/components/schemas/Kind",
+            "#/paths/~1api~1customers~1{id}~1kind/post",
+        ]
+    );
+
+    // The rest of the group is still generated
+    let customers = project.read("endpoints/customers.ts");
+    assert!(customers.contains("getCustomer"));
+    assert!(!customers.contains("setCustomerKind"));
+}
+
+#[test]
+fn reports_malformed_generic_arguments() {
+    let mut swagger = fixture("recursive_schemas.json");
+    swagger["components"]["schemas"]["TreeNode_CategoryDto"]["x-mtr"]["ga"] =
+        serde_json::json!(["Foo"]);
+    let project = Project::with_swagger("malformed-generics", "", &swagger);
+
+    let output = project.run(&[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+    assert!(
+        stderr.contains("Failed to parse schema TreeNode_CategoryDto: Invalid generic arguments: Generic type \"Foo\" isn't a schema reference at #/components/schemas/TreeNode_CategoryDto"),
+        "{}",
+        stderr
+    );
+}
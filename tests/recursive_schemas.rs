@@ -0,0 +1,83 @@
+mod common;
+
+use common::{fixture, Project};
+use std::process::Output;
+
+const SETTINGS: &str = "revive_dates = true\n";
+
+// Runs mortar against a fixture in a scratch directory, returning the project and process output
+fn run_mortar(name: &str, fixture_name: &str) -> (Project, Output) {
+    let project = Project::with_swagger(name, SETTINGS, &fixture(fixture_name));
+    let output = project.run(&[]);
+
+    (project, output)
+}
+
+#[test]
+fn generates_recursive_and_mutually_recursive_types() {
+    let (project, output) = run_mortar("recursive", "recursive_schemas.json");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // Self references stay within the file
+    let catalog = project.read("Shop/Catalog.ts");
+    assert!(catalog.contains("children: CategoryDto[];"));
+    assert!(catalog.contains("parent: CategoryDto;"));
+    assert!(!catalog.contains("from \"mortar/Shop/Catalog\""));
+    assert!(catalog.contains("children: () => CategoryDtoDates,"));
+    // Nullable references start out empty rather than creating the type within itself
+    assert!(catalog.contains("parent: null,"));
+
+    // Generic trees refer back to themselves with the same arguments
+    let common = project.read("Shop/Common.ts");
+    assert!(common.contains("children: TreeNode<T0>[];"));
+    assert!(common.contains("value: T0;"));
+    assert!(common.contains("children: () => TreeNodeDates(t0),"));
+
+    // Mutually referencing namespaces import each other
+    let orders = project.read("Shop/Orders.ts");
+    assert!(orders.contains(
+        "import {\n    CustomerDto,\n    CustomerDtoDates,\n    defaultCustomerDto,\n} from \"mortar/Shop/Customers\";"
+    ));
+    assert!(orders.contains("customer: defaultCustomerDto(),"));
+    let customers = project.read("Shop/Customers.ts");
+    assert!(customers.contains("import { OrderDto, OrderDtoDates } from \"mortar/Shop/Orders\";"));
+
+    let catalog_endpoints = project.read("endpoints/catalog.ts");
+    assert!(catalog_endpoints.contains("TreeNodeDates(() => CategoryDtoDates)"));
+}
+
+#[test]
+fn reports_generic_argument_cycles() {
+    let (_project, output) = run_mortar("generic-cycle", "generic_cycle.json");
+    assert!(!output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(
+            "Generic type arguments form a cycle: Box_Wrapper -> Wrapper_Box -> Box_Wrapper"
+        ),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn reports_self_references_without_a_default() {
+    let mut swagger = fixture("recursive_schemas.json");
+    swagger["components"]["schemas"]["CategoryDto"]["properties"]["parent"] =
+        serde_json::json!({ "$ref": "#/components/schemas/CategoryDto" });
+    let project = Project::with_swagger("recursive-default", SETTINGS, &swagger);
+
+    let output = project.run(&[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("CategoryDto leads back to this type so has no default, make it nullable or declare a default at #/components/schemas/CategoryDto/properties/parent"),
+        "{}",
+        stderr
+    );
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use common::{assert_contains, generate};
+
+#[test]
+fn returns_the_union_of_success_responses() {
+    let project = generate("responses", "", "responses.json");
+    let orders = project.read("endpoints/orders.ts");
+
+    assert_contains(
+        &orders,
+        "apiPost<OrderDto | QuoteDto, \"orders/createOrder\">(",
+    );
+    // Unions of array items keep their parentheses
+    assert_contains(
+        &orders,
+        "apiGet<(OrderDto | QuoteDto)[], \"orders/listOrders\">(",
+    );
+    // Successes without content can resolve to nothing
+    assert_contains(&orders, "apiPut<OrderDto | void, \"orders/updateOrder\">(");
+}
+
+#[test]
+fn types_the_error_responses_of_an_action() {
+    let project = generate("error-responses", "", "responses.json");
+    let orders = project.read("endpoints/orders.ts");
+
+    assert_contains(
+        &orders,
+        "export interface CreateOrderErrors { 400: ProblemDetails; 404: void; \"5XX\": ProblemDetails; }",
+    );
+    assert_contains(
+        &orders,
+        "export const createOrder = withErrors<CreateOrderErrors>([400, 404, \"5XX\"])( makeAction((",
+    );
+    // Actions without error responses aren't wrapped
+    assert_contains(&orders, "export const listOrders = makeAction((");
+
+    assert_contains(
+        &project.read("lib.ts"),
+        "export function withErrors<TErrors>(statuses: (keyof TErrors)[]) {",
+    );
+}
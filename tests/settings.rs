@@ -0,0 +1,89 @@
+mod common;
+
+use common::Project;
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn rejects_unknown_settings_with_suggestions() {
+    let project = Project::new("settings");
+    project.write(
+        "portal.toml",
+        "output_dir = \"./output\"\nrevive_date = true\nprevent_update = true\n[http]\nbearer_tokn = \"x\"\n",
+    );
+
+    let output = project
+        .mortar()
+        .args(["config", "show", "--config", "portal.toml"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Unknown setting revive_date in portal.toml, did you mean revive_dates?"),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("Unknown setting prevent_update in portal.toml\n"),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("Unknown setting http.bearer_tokn in portal.toml, did you mean bearer_token?"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn shows_where_settings_come_from() {
+    let project = Project::new("show");
+    project.write("mortar.toml", "output_dir = \"./output\"\nrevive_dates = true\n");
+
+    let output = project
+        .mortar()
+        .args(["config", "show", "--output-dir", "./elsewhere"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("output_dir = \"./elsewhere\" # from the command line"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("revive_dates = true # from mortar.toml"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("enum_style = \"const_object\" # from default"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn committed_json_schema_matches_settings() {
+    let output = Project::new("schema")
+        .mortar()
+        .args(["config", "schema"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let committed =
+        fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("mortar.schema.json")).unwrap();
+    assert!(
+        String::from_utf8_lossy(&output.stdout).trim() == committed.replace("\r\n", "\n").trim(),
+        "mortar.schema.json is out of date, regenerate it with `cargo run -- config schema > mortar.schema.json`"
+    );
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use common::{assert_contains, fixture, Project};
+
+const TARGETS: &str = r#"
+[[targets]]
+name = "shop"
+swagger_file = "shop.json"
+output_dir = "./shop"
+action_groups = ["catalog"]
+
+[[targets]]
+name = "orders"
+swagger_file = "orders.json"
+output_dir = "./orders"
+"#;
+
+fn setup(name: &str) -> Project {
+    let project = Project::new(name);
+    project.write("mortar.toml", TARGETS);
+    project.write("shop.json", fixture("recursive_schemas.json").to_string());
+    project.write("orders.json", fixture("responses.json").to_string());
+    project
+}
+
+#[test]
+fn generates_every_target() {
+    let project = setup("targets");
+
+    let output = project.mortar().output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let read = |file: &str| std::fs::read_to_string(project.dir.join(file)).unwrap();
+    assert_contains(
+        &read("shop/endpoints/catalog.ts"),
+        "export const getCategoryTree = makeAction((",
+    );
+    // Limited to its action groups
+    assert!(!project.exists("shop/endpoints/customers.ts"));
+    assert_contains(
+        &read("orders/endpoints/orders.ts"),
+        "apiPost<OrderDto | QuoteDto, \"orders/createOrder\">(",
+    );
+}
+
+#[test]
+fn finishes_the_other_targets_when_one_fails() {
+    let project = setup("failing-targets");
+    project.write("shop.json", "{ not json");
+
+    let output = project.mortar().output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("Failed to generate shop"), "{}", stderr);
+    assert!(project.exists("orders/endpoints/orders.ts"));
+
+    // Every failure is reported
+    project.write("orders.json", "{ not json");
+    let output = project.mortar().output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("Failed to generate shop"), "{}", stderr);
+    assert!(stderr.contains("Failed to generate orders"), "{}", stderr);
+    assert!(stderr.contains("2 targets failed"), "{}", stderr);
+}
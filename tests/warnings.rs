@@ -0,0 +1,114 @@
+mod common;
+
+use common::{fixture, Project};
+use std::process::Output;
+
+// The recursive fixture with a property referring to a schema that doesn't exist
+fn swagger() -> serde_json::Value {
+    let mut swagger = fixture("recursive_schemas.json");
+    swagger["components"]["schemas"]["CategoryDto"]["properties"]["owner"] =
+        serde_json::json!({ "$ref": "#/components/schemas/Missing" });
+
+    swagger
+}
+
+fn setup(name: &str) -> Project {
+    Project::with_swagger(name, "", &swagger())
+}
+
+#[test]
+fn summarises_warnings() {
+    let project = setup("warnings");
+
+    let output = project.run(&[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(
+        stderr.contains("WARN: Generated ./output with 1 warning (1 unresolved reference)"),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("Unable to find schema #/components/schemas/Missing at #/components/schemas/CategoryDto/properties/owner"),
+        "{}",
+        stderr
+    );
+
+    let catalog = project.read("Shop/Catalog.ts");
+    assert!(catalog.contains("owner: any;"));
+}
+
+#[test]
+fn deny_warnings_fails_the_run() {
+    let project = setup("deny-warnings");
+
+    let output = project.run(&["--deny-warnings", "-q"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("./output raised warnings and --deny-warnings is set"),
+        "{}",
+        stderr
+    );
+}
+
+fn diagnostics(output: &Output) -> Vec<serde_json::Value> {
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn prints_json_diagnostics() {
+    let project = setup("json-warnings");
+
+    let output = project.run(&["--message-format", "json"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let warning = &diagnostics(&output)[0];
+    assert_eq!(warning["code"], "unresolved-reference");
+    assert_eq!(warning["severity"], "warning");
+    assert_eq!(warning["target"], "./output");
+    assert_eq!(warning["pointer"], "#/components/schemas/CategoryDto/properties/owner");
+    assert_eq!(warning["message"], "Unable to find schema #/components/schemas/Missing");
+    assert!(warning["suggestion"].is_string());
+}
+
+#[test]
+fn points_any_fallbacks_at_their_schema() {
+    let mut swagger = swagger();
+    swagger["components"]["schemas"]["CategoryDto"]["properties"]["extra"] = serde_json::json!({});
+    let project = Project::with_swagger("json-fallbacks", "", &swagger);
+
+    let output = project.run(&["--message-format", "json"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let fallbacks = diagnostics(&output)
+        .into_iter()
+        .filter(|d| d["code"] == "any-fallback")
+        .collect::<Vec<_>>();
+    assert_eq!(fallbacks.len(), 1, "{:?}", fallbacks);
+    assert_eq!(fallbacks[0]["pointer"], "#/components/schemas/CategoryDto/properties/extra");
+}
+
+#[test]
+fn prints_json_errors() {
+    let project = Project::with_swagger(
+        "json-errors",
+        "banned_namespaces = [\"Shop/Orders\"]\n",
+        &swagger(),
+    );
+
+    let output = project.run(&["--message-format", "json"]);
+    assert!(!output.status.success());
+
+    let errors = diagnostics(&output)
+        .into_iter()
+        .filter(|d| d["severity"] == "error")
+        .collect::<Vec<_>>();
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!(errors[0]["code"], "banned-namespace");
+    assert_eq!(errors[0]["pointer"], "#/components/schemas/OrderDto");
+    assert_eq!(errors[0]["target"], "./output");
+}
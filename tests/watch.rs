@@ -0,0 +1,69 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::{fixture_path, serve, Project};
+use std::fs;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[test]
+fn keeps_watching_after_a_failed_run_and_stops_on_sigterm() {
+    // A backend that serves a broken swagger.json, restarts with a working one, then holds the build id until killed
+    let swagger = fs::read(fixture_path("recursive_schemas.json")).unwrap();
+    let builds = AtomicUsize::new(0);
+    let address = serve(move |request, _| {
+        if request.starts_with("GET /mortar/buildId") {
+            let build = builds.fetch_add(1, Ordering::SeqCst);
+            // Hangs as a backend that hasn't restarted would
+            (build < 2).then(|| format!("build-{}", build).into_bytes())
+        } else if builds.load(Ordering::SeqCst) < 2 {
+            Some(b"{ not json".to_vec())
+        } else {
+            Some(swagger.clone())
+        }
+    });
+
+    let project = Project::new("watch");
+    project.write(
+        "mortar.toml",
+        format!(
+            "output_dir = \"./output\"\n\
+             swagger_endpoint = \"{0}/api/swagger.json\"\n\
+             mortar_endpoint = \"{0}/mortar/buildId\"\n",
+            address
+        ),
+    );
+
+    let mortar = project
+        .mortar()
+        .arg("--watch")
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let started = Instant::now();
+    while !project.exists("output/Shop/Catalog.ts") {
+        assert!(
+            started.elapsed() < Duration::from_secs(30),
+            "Never regenerated"
+        );
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    let killed = Command::new("kill")
+        .args(["-TERM", &mortar.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(killed.success());
+
+    let output = mortar.wait_with_output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(stderr.contains("Failed to generate ./output"), "{}", stderr);
+    assert!(stderr.contains("Still watching ./output"), "{}", stderr);
+    assert!(stderr.contains("Generated ./output"), "{}", stderr);
+    assert!(stderr.contains("Stopping"), "{}", stderr);
+}